@@ -1,6 +1,24 @@
-use crossterm::event::KeyCode;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
-use crate::features::{tasks::TaskState, timers::TimerState};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+
+use chrono::{DateTime, Local};
+use tui::layout::Rect;
+
+use crate::{
+    config::{self, IdleConfig},
+    features::{
+        reports::ReportState,
+        tasks::{TaskEvent, TaskState},
+        timers::{IdleReason, TimerState},
+    },
+    keymap::{self, Action, KeyScope, Keymap, PendingKeys},
+    storage,
+    theme::{self, Theme},
+};
 
 pub struct TabsState<'a> {
     pub titles: Vec<&'a str>,
@@ -8,7 +26,7 @@ pub struct TabsState<'a> {
 }
 
 impl<'a> TabsState<'a> {
-    pub fn new(titles: Vec<&'a str>) -> TabsState {
+    pub fn new(titles: Vec<&'a str>) -> TabsState<'a> {
         TabsState { titles, index: 0 }
     }
 
@@ -28,15 +46,37 @@ impl<'a> TabsState<'a> {
 pub enum AppTab {
     Tasks,
     Timers,
+    Reports,
 }
 
 pub fn get_menu_item_title(menu_item: AppTab) -> &'static str {
     match menu_item {
         AppTab::Tasks => "Tasks",
         AppTab::Timers => "Timers",
+        AppTab::Reports => "Reports",
     }
 }
 
+/// Screen areas recorded while drawing, used to find what a mouse event points at.
+#[derive(Default)]
+pub struct MouseAreas {
+    pub tabs: Vec<Rect>,
+    /// Area of every list row on screen together with its row number.
+    pub list_rows: Vec<(Rect, usize)>,
+    pub popup: Option<Rect>,
+}
+
+fn contains(area: Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
+}
+
+/// Longest time between two clicks on a row that still counts as a double click.
+const DOUBLE_CLICK_DURATION: Duration = Duration::from_millis(500);
+/// How long a notification stays in the header.
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(5);
+/// A gap this long between two ticks means the app was not running, e.g. during suspend.
+const SUSPEND_GAP_SECONDS: i64 = 120;
+
 pub struct App<'a> {
     // App state
     pub title: &'a str,
@@ -45,65 +85,255 @@ pub struct App<'a> {
     // Feature state definitions
     pub task_state: TaskState,
     pub timer_state: TimerState,
+    pub report_state: ReportState,
+
+    pub keymap: Keymap,
+    pub pending_keys: PendingKeys,
+    pub mouse_areas: MouseAreas,
+    last_click: Option<(usize, Instant)>,
+    pub help_enabled: bool,
 
     pub should_quit: bool,
+    pub notification: Option<(String, Instant)>,
+    pub should_ring_bell: bool,
+
+    idle_config: IdleConfig,
+    last_input_at: DateTime<Local>,
+    last_tick_at: DateTime<Local>,
 
     // Internals
+    #[allow(dead_code)]
     pub display_debugger: bool,
     pub enhanced_graphics: bool,
+    pub theme: Theme,
 }
 
 impl<'a> App<'a> {
-    pub fn new(title: &'a str, enhanced_graphics: bool) -> App<'a> {
-        App {
+    pub fn new(title: &'a str, enhanced_graphics: bool) -> io::Result<App<'a>> {
+        let config = config::load()?;
+        let enhanced_graphics = enhanced_graphics && config.theme.enhanced_graphics;
+        let theme = theme::load(&config, enhanced_graphics)?;
+        let keymap = keymap::load()?;
+        let stored_state = storage::load()?;
+        // Timers stay linked to the ids of deleted tasks, those ids must not come back.
+        let next_task_id = stored_state
+            .timers
+            .iter()
+            .filter_map(|timer| timer.task_id)
+            .map(|task_id| task_id + 1)
+            .fold(stored_state.next_task_id, u64::max);
+
+        Ok(App {
             title,
 
             should_quit: false,
+            notification: None,
+            should_ring_bell: false,
+
+            idle_config: config.idle,
+            last_input_at: Local::now(),
+            last_tick_at: Local::now(),
 
             tabs: TabsState::new(vec![
                 get_menu_item_title(AppTab::Tasks),
                 get_menu_item_title(AppTab::Timers),
+                get_menu_item_title(AppTab::Reports),
             ]),
 
-            task_state: TaskState::new(),
-            timer_state: TimerState::new(),
+            task_state: TaskState::new(stored_state.tasks, next_task_id),
+            timer_state: TimerState::new(stored_state.timers, config.pomodoro, config.timers),
+            report_state: ReportState::default(),
+
+            keymap,
+            pending_keys: PendingKeys::default(),
+            mouse_areas: MouseAreas::default(),
+            last_click: None,
+            help_enabled: false,
 
             enhanced_graphics,
+            theme,
             display_debugger: false,
-        }
+        })
     }
 
     // TODO: Implement tab focusing or active state selection
-    pub fn on_keycode(&mut self, key: KeyCode) {
-        // Tab index == 0 is TaskList
-        if self.tabs.index == 0 && self.task_state.on_keycode(key) {
+    pub fn on_key(&mut self, key: KeyEvent) {
+        self.last_input_at = Local::now();
+        // The idle prompt takes every key until answered, no matter which tab is open.
+        if self.timer_state.idle_prompt.is_some() {
+            self.timer_state.on_idle_key(key, &self.keymap);
             return;
         }
-        // Tab index == 1 is Timers
-        if self.tabs.index == 1 && self.timer_state.on_keycode(key) {
+
+        // Any key closes the help popup without doing anything else
+        if self.help_enabled {
+            self.help_enabled = false;
+            self.pending_keys.clear();
             return;
         }
 
-        match key {
-            KeyCode::Char(c) => match c {
-                '1' => {
-                    self.tabs.index = 0;
-                }
-                '2' => {
-                    self.tabs.index = 1;
+        // Popups and input bars read keys as text, the help has a key in the popup bindings
+        if self.is_editing() && self.keymap.action(KeyScope::Popup, key) == Some(Action::Help) {
+            self.help_enabled = true;
+            return;
+        }
+
+        // Popups, input bars and search results take keys before the keymap
+        let is_handled = match self.tabs.index {
+            0 => self.task_state.on_key(key, &self.keymap),
+            1 => self.timer_state.on_key(key, &self.keymap),
+            _ => false,
+        };
+        if is_handled {
+            self.pending_keys.clear();
+            self.apply_tab_events();
+            return;
+        }
+
+        if let Some((action, count)) = self.pending_keys.push(&self.keymap, self.scope(), key) {
+            self.on_action(action, count);
+        }
+    }
+
+    pub fn on_mouse(&mut self, mouse: MouseEvent) {
+        self.last_input_at = Local::now();
+        if self.timer_state.idle_prompt.is_some() {
+            return;
+        }
+
+        let (column, row) = (mouse.column, mouse.row);
+        if let Some(popup) = self.mouse_areas.popup {
+            if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+                // The help can be open on top of a popup, which stays open
+                if self.help_enabled {
+                    self.help_enabled = contains(popup, column, row);
+                } else if !contains(popup, column, row) {
+                    self.task_state.close_popup();
+                    self.timer_state.close_popup();
                 }
-                'q' => {
-                    self.should_quit = true;
+            }
+            return;
+        }
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(index) = self
+                    .mouse_areas
+                    .tabs
+                    .iter()
+                    .position(|area| contains(*area, column, row))
+                {
+                    self.tabs.index = index;
+                    self.last_click = None;
+                } else if let Some((_, list_row)) = self
+                    .mouse_areas
+                    .list_rows
+                    .iter()
+                    .find(|(area, _)| contains(*area, column, row))
+                {
+                    self.on_row_click(*list_row);
                 }
-                _ => {}
-            },
+            }
+            MouseEventKind::ScrollDown => self.on_action(Action::MoveDown, Some(1)),
+            MouseEventKind::ScrollUp => self.on_action(Action::MoveUp, Some(1)),
+            _ => {}
+        };
+    }
+
+    /// Selects the clicked row, a second click on it toggles it like the toggle action.
+    fn on_row_click(&mut self, list_row: usize) {
+        let is_double_click = self.last_click.is_some_and(|(last_row, clicked_at)| {
+            last_row == list_row && clicked_at.elapsed() <= DOUBLE_CLICK_DURATION
+        });
+        match self.scope() {
+            KeyScope::Tasks => self.task_state.tasks.select_row(list_row),
+            KeyScope::Timers => self.timer_state.timers.select_row(list_row),
+            _ => return,
+        };
 
-            KeyCode::Left => self.on_left(),
-            KeyCode::Right => self.on_right(),
+        if is_double_click {
+            self.last_click = None;
+            self.on_action(Action::Toggle, None);
+        } else {
+            self.last_click = Some((list_row, Instant::now()));
+        }
+    }
 
-            KeyCode::Esc => {}
-            _ => {}
+    /// Keymap scope of the open tab.
+    pub fn scope(&self) -> KeyScope {
+        match self.tabs.index {
+            0 => KeyScope::Tasks,
+            1 => KeyScope::Timers,
+            _ => KeyScope::Reports,
+        }
+    }
+
+    /// Whether a popup or an input bar of the open tab takes the keys.
+    fn is_editing(&self) -> bool {
+        match self.tabs.index {
+            0 => self.task_state.is_editing(),
+            1 => self.timer_state.is_editing(),
+            _ => false,
+        }
+    }
+
+    /// Keymap scopes keys currently go through, the first scope binding a key gets it.
+    pub fn active_scopes(&self) -> Vec<KeyScope> {
+        if self.is_editing() {
+            return vec![KeyScope::Input, KeyScope::Popup];
+        }
+        let is_search_active = match self.tabs.index {
+            0 => self.task_state.search_bar.query.is_some(),
+            1 => self.timer_state.search_bar.query.is_some(),
+            _ => false,
         };
+
+        let mut scopes = vec![];
+        if is_search_active {
+            scopes.push(KeyScope::Search);
+        }
+        scopes.extend([self.scope(), KeyScope::Global]);
+
+        scopes
+    }
+
+    fn on_action(&mut self, action: Action, count: Option<usize>) {
+        match action {
+            Action::ShowTasks => self.tabs.index = 0,
+            Action::ShowTimers => self.tabs.index = 1,
+            Action::ShowReports => self.tabs.index = 2,
+            Action::NextTab => self.on_right(),
+            Action::PreviousTab => self.on_left(),
+            Action::Help => self.help_enabled = true,
+            Action::Quit => self.should_quit = true,
+            action => match self.scope() {
+                KeyScope::Tasks => self.task_state.on_action(action, count),
+                KeyScope::Timers => self.timer_state.on_action(action, count),
+                _ => self.report_state.on_action(action),
+            },
+        };
+        self.apply_tab_events();
+    }
+
+    /// Forwards what the tasks and timers reported back after handling a key.
+    fn apply_tab_events(&mut self) {
+        self.apply_task_events();
+        if let Some(message) = self.timer_state.take_notification() {
+            self.notify(message);
+        }
+    }
+
+    /// Keeps timers in step with the tasks they track.
+    fn apply_task_events(&mut self) {
+        for event in self.task_state.take_events() {
+            match event {
+                TaskEvent::TrackTime { task_id, title } => {
+                    self.notify(format!("Tracking time on \"{}\"", title));
+                    self.timer_state.track_task(task_id, title);
+                }
+                TaskEvent::Completed(task_id) => self.timer_state.stop_task_timers(task_id),
+            };
+        }
     }
 
     pub fn on_right(&mut self) {
@@ -115,6 +345,67 @@ impl<'a> App<'a> {
     }
 
     pub fn on_tick(&mut self) {
-        self.timer_state.on_tick();
+        self.detect_idle();
+        self.pending_keys.expire();
+
+        // Timer totals are derived from wall-clock sessions, only pomodoro phases need advancing.
+        let alerts = self.timer_state.on_tick();
+        if !alerts.is_empty() {
+            self.notify(alerts.join(" | "));
+            self.should_ring_bell = true;
+        }
+
+        if self
+            .notification
+            .as_ref()
+            .is_some_and(|(_, shown_at)| shown_at.elapsed() >= NOTIFICATION_DURATION)
+        {
+            self.notification = None;
+        }
+    }
+
+    /// Marks time that running timers counted while nobody was around.
+    fn detect_idle(&mut self) {
+        let now = Local::now();
+        let last_tick_at = std::mem::replace(&mut self.last_tick_at, now);
+        if self.timer_state.idle_prompt.is_some() || self.timer_state.running_timers().is_empty() {
+            return;
+        }
+
+        if (now - last_tick_at).num_seconds() >= SUSPEND_GAP_SECONDS {
+            self.timer_state
+                .mark_idle(IdleReason::Suspended, last_tick_at, Some(now));
+            self.should_ring_bell = true;
+        } else if self.idle_config.minutes > 0
+            && (now - self.last_input_at).num_minutes() >= self.idle_config.minutes as i64
+        {
+            self.timer_state
+                .mark_idle(IdleReason::NoInput, self.last_input_at, None);
+        }
+    }
+
+    pub fn notify(&mut self, message: String) {
+        self.notification = Some((message, Instant::now()));
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        storage::save(
+            &self.task_state.tasks.items,
+            &self.timer_state.timers.items,
+            self.task_state.next_task_id(),
+        )?;
+
+        self.task_state.is_dirty = false;
+        self.timer_state.is_dirty = false;
+
+        Ok(())
+    }
+
+    pub fn save_if_dirty(&mut self) -> io::Result<()> {
+        if self.task_state.is_dirty || self.timer_state.is_dirty {
+            self.save()?;
+        }
+
+        Ok(())
     }
 }
@@ -0,0 +1,83 @@
+use std::{error::Error, fs, io::Write, path::PathBuf};
+
+use chrono::Local;
+
+use crate::{
+    features::{
+        export::{collect_records, parse_export_date, render, ExportFormat, ExportOptions},
+        timers::start_of_week,
+    },
+    storage,
+};
+
+pub const USAGE: &str = "\
+Usage:
+  productivity-tui                   Start the terminal UI
+  productivity-tui export [OPTIONS]  Export timer sessions as a timesheet
+
+Export options:
+  --format <csv|json|timewarrior>  Output format (default: csv)
+  --from <DATE>                    First day, e.g. 2024-05-01 or -7d (default: start of week)
+  --to <DATE>                      Last day, e.g. today or yesterday (default: today)
+  --output <FILE>                  File to write (default: stdout)
+";
+
+pub enum Command {
+    Tui,
+    Export(ExportOptions),
+    Help,
+}
+
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    match args.next().as_deref() {
+        None => Ok(Command::Tui),
+        Some("-h" | "--help" | "help") => Ok(Command::Help),
+        Some("export") => parse_export(args).map(Command::Export),
+        Some(command) => Err(format!("Unknown command \"{}\"", command)),
+    }
+}
+
+fn parse_export(mut args: impl Iterator<Item = String>) -> Result<ExportOptions, String> {
+    let today = Local::now().date_naive();
+    let mut options = ExportOptions {
+        format: ExportFormat::Csv,
+        from: start_of_week(today),
+        to: today,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--format" => options.format = ExportFormat::parse(&value()?)?,
+            "--from" => options.from = parse_export_date(&value()?, today)?,
+            "--to" => options.to = parse_export_date(&value()?, today)?,
+            "--output" => options.output = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("Unknown export option \"{}\"", arg)),
+        };
+    }
+    if options.to < options.from {
+        return Err("The range has to end after it starts".to_owned());
+    }
+
+    Ok(options)
+}
+
+pub fn export(options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+    let stored_state = storage::load()?;
+    let records = collect_records(&stored_state.timers, options.from, options.to);
+    let contents = render(options.format, &records);
+
+    match &options.output {
+        Some(path) => {
+            fs::write(path, contents)?;
+            eprintln!("Exported {} records to {}", records.len(), path.display());
+        }
+        None => std::io::stdout().write_all(contents.as_bytes())?,
+    };
+
+    Ok(())
+}
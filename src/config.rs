@@ -0,0 +1,182 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::theme::CustomTheme;
+
+const APP_DIRECTORY: &str = "productivity-tui";
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+/// Largest number of minutes a setting may hold, longer times don't fit a chrono duration.
+const MAX_MINUTES: u64 = i64::MAX as u64 / 60_000;
+
+/// User settings, every missing key falls back to its default.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub pomodoro: PomodoroConfig,
+    pub timers: TimersConfig,
+    pub idle: IdleConfig,
+    pub theme: ThemeConfig,
+    /// User-defined themes by name.
+    pub themes: BTreeMap<String, CustomTheme>,
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), String> {
+        self.pomodoro.validate()?;
+        self.idle.validate()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// "dark", "light", "high-contrast", "16-color" or the name of a user-defined theme.
+    pub name: String,
+    /// Unicode borders and the full palette, turn off for terminals that lack them.
+    pub enhanced_graphics: bool,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: String::from("dark"),
+            enhanced_graphics: true,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdleConfig {
+    /// Minutes without key input after which running timers count as idle, 0 disables it.
+    pub minutes: u64,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self { minutes: 10 }
+    }
+}
+
+impl IdleConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.minutes > MAX_MINUTES {
+            return Err(format!("minutes in [idle] can be at most {}", MAX_MINUTES));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimersConfig {
+    /// Starting a timer pauses every other running timer.
+    pub exclusive: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PomodoroConfig {
+    pub work_minutes: u64,
+    pub short_break_minutes: u64,
+    pub long_break_minutes: u64,
+    /// Number of work phases before a long break.
+    pub long_break_interval: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_interval: 4,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// Phases of 0 minutes would end as soon as they started, over and over again.
+    /// Longer phases than `MAX_MINUTES` don't fit the durations they are counted with.
+    fn validate(&self) -> Result<(), String> {
+        for (name, minutes) in [
+            ("work_minutes", self.work_minutes),
+            ("short_break_minutes", self.short_break_minutes),
+            ("long_break_minutes", self.long_break_minutes),
+        ] {
+            if minutes == 0 {
+                return Err(format!("{} in [pomodoro] has to be at least 1", name));
+            }
+            if minutes > MAX_MINUTES {
+                return Err(format!(
+                    "{} in [pomodoro] can be at most {}",
+                    name, MAX_MINUTES
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Path of `file_name` inside the app's directory in the XDG config directory.
+pub fn config_file_path(file_name: &str) -> io::Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join(APP_DIRECTORY).join(file_name))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not determine the config directory",
+            )
+        })
+}
+
+/// Loads the config file, falling back to the defaults when there is none.
+pub fn load() -> io::Result<Config> {
+    let path = config_file_path(CONFIG_FILE_NAME)?;
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    toml::from_str(&contents)
+        .map_err(|error| error.to_string())
+        .and_then(|config: Config| config.validate().map(|()| config))
+        .map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), error),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(contents).map_err(|error| error.to_string())?;
+        config.validate().map(|()| config)
+    }
+
+    #[test]
+    fn rejects_idle_minutes_that_overflow() {
+        assert!(parse("[idle]\nminutes = 0").is_ok());
+        assert!(parse(&format!("[idle]\nminutes = {}", MAX_MINUTES)).is_ok());
+        assert!(parse(&format!("[idle]\nminutes = {}", MAX_MINUTES + 1)).is_err());
+    }
+
+    #[test]
+    fn rejects_pomodoro_minutes_that_overflow() {
+        assert!(parse("[pomodoro]\nwork_minutes = 0").is_err());
+        assert!(parse(&format!("[pomodoro]\nwork_minutes = {}", MAX_MINUTES)).is_ok());
+        assert!(parse(&format!(
+            "[pomodoro]\nlong_break_minutes = {}",
+            MAX_MINUTES + 1
+        ))
+        .is_err());
+        assert!(parse(&format!("[pomodoro]\nshort_break_minutes = {}", i64::MAX)).is_err());
+    }
+}
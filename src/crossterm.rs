@@ -6,7 +6,7 @@ use crossterm::{
 };
 use std::{
     error::Error,
-    io,
+    io::{self, Write},
     time::{Duration, Instant},
 };
 use tui::{
@@ -15,6 +15,9 @@ use tui::{
 };
 
 pub fn run(tick_rate: Duration, enhanced_graphics: bool) -> Result<(), Box<dyn Error>> {
+    // Load persisted state before touching the terminal so errors stay readable
+    let app = App::new("Productivity Terminal UI", enhanced_graphics)?;
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -22,8 +25,7 @@ pub fn run(tick_rate: Duration, enhanced_graphics: bool) -> Result<(), Box<dyn E
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Create terminal app and run it
-    let app = App::new("Productivity Terminal UI", enhanced_graphics);
+    // Run the terminal app
     let res = run_app(&mut terminal, app, tick_rate);
 
     // Restore terminal
@@ -56,9 +58,11 @@ fn run_app<B: Backend>(
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                app.on_keycode(key.code);
-            }
+            match event::read()? {
+                Event::Key(key) => app.on_key(key),
+                Event::Mouse(mouse) => app.on_mouse(mouse),
+                _ => {}
+            };
         }
 
         // Handle tick_rate based logic.
@@ -67,9 +71,18 @@ fn run_app<B: Backend>(
             last_tick = Instant::now();
         }
 
+        if app.should_ring_bell {
+            let mut stdout = io::stdout();
+            stdout.write_all(b"\x07")?;
+            stdout.flush()?;
+            app.should_ring_bell = false;
+        }
+
         // Quit app (break infinite loop)
         if app.should_quit {
-            return Ok(());
+            return app.save();
         }
+
+        app.save_if_dirty()?;
     }
 }
@@ -0,0 +1,526 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Days, Local, NaiveDate, SecondsFormat, Utc};
+use crossterm::event::KeyEvent;
+use serde::Serialize;
+
+use crate::{
+    features::timers::{format_duration, start_of_day, start_of_week, Timer},
+    keymap::{Action, KeyScope, Keymap},
+    structures::text_input::TextInput,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Timewarrior,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "timewarrior" | "timew" => Ok(ExportFormat::Timewarrior),
+            format => Err(format!(
+                "Unknown export format \"{}\", use csv, json or timewarrior",
+                format
+            )),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Timewarrior => "data",
+        }
+    }
+}
+
+/// Parses a past date such as "2024-05-01", "today", "yesterday", "-3d" or "-2w", as used for
+/// export ranges and adjustments.
+pub fn parse_export_date(value: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let value = value.trim().to_lowercase();
+
+    match value.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return Ok(today - Days::new(1)),
+        _ => {}
+    };
+
+    if let Some(offset) = value.strip_prefix('-') {
+        let (amount, days_per_unit) = match (offset.strip_suffix('d'), offset.strip_suffix('w')) {
+            (Some(amount), _) => (amount, Some(1)),
+            (_, Some(amount)) => (amount, Some(7)),
+            _ => (offset, None),
+        };
+        if let (Ok(amount), Some(days_per_unit)) = (amount.parse::<u64>(), days_per_unit) {
+            return amount
+                .checked_mul(days_per_unit)
+                .and_then(|days| today.checked_sub_days(Days::new(days)))
+                .ok_or_else(|| format!("Date \"{}\" is too far back", value));
+        }
+    }
+
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .map_err(|_| format!("Unrecognized date \"{}\"", value))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportRecordKind {
+    Session,
+    /// Manual change of the timer total, started and ended at the moment it counts towards.
+    Adjustment,
+}
+
+/// Single timer session or adjustment inside the exported range.
+#[derive(Serialize)]
+pub struct ExportRecord {
+    pub kind: ExportRecordKind,
+    pub title: String,
+    pub start: DateTime<Local>,
+    /// Unset for a session that is still running.
+    pub end: Option<DateTime<Local>>,
+    /// Negative for adjustments that subtracted time.
+    pub duration_seconds: i64,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    /// Description of an adjustment, unset for sessions.
+    pub description: Option<String>,
+}
+
+/// Sessions and adjustments of all timers between `from` and `to` (both inclusive), cut
+/// to the range and ordered by start.
+pub fn collect_records(timers: &[Timer], from: NaiveDate, to: NaiveDate) -> Vec<ExportRecord> {
+    let range_start = start_of_day(from);
+    let range_end = start_of_day(to + Days::new(1));
+    let now = Local::now();
+
+    let mut records: Vec<ExportRecord> = timers
+        .iter()
+        .flat_map(|timer| {
+            timer.sessions.iter().filter_map(move |session| {
+                let start = session.start.max(range_start);
+                let end = session.end.map(|end| end.min(range_end));
+                let duration = (end.unwrap_or(now).min(range_end) - start).to_std().ok()?;
+                if duration.is_zero() {
+                    return None;
+                }
+
+                Some(ExportRecord {
+                    kind: ExportRecordKind::Session,
+                    title: timer.title.clone(),
+                    start,
+                    end: end.or_else(|| (now > range_end).then_some(range_end)),
+                    duration_seconds: duration.as_secs() as i64,
+                    tags: timer.tags.clone(),
+                    project: timer.project.clone(),
+                    description: None,
+                })
+            })
+        })
+        .collect();
+    records.extend(timers.iter().flat_map(|timer| {
+        timer
+            .adjustments
+            .iter()
+            .filter(|adjustment| {
+                adjustment.offset_seconds != 0
+                    && (range_start..range_end).contains(&adjustment.counts_at())
+            })
+            .map(|adjustment| ExportRecord {
+                kind: ExportRecordKind::Adjustment,
+                title: timer.title.clone(),
+                start: adjustment.counts_at(),
+                end: Some(adjustment.counts_at()),
+                duration_seconds: adjustment.offset_seconds,
+                tags: timer.tags.clone(),
+                project: timer.project.clone(),
+                description: Some(adjustment.description.clone()),
+            })
+    }));
+    records.sort_by_key(|record| record.start);
+
+    records
+}
+
+pub fn render(format: ExportFormat, records: &[ExportRecord]) -> String {
+    match format {
+        ExportFormat::Csv => render_csv(records),
+        ExportFormat::Json => serde_json::to_string_pretty(records).unwrap_or_default() + "\n",
+        ExportFormat::Timewarrior => render_timewarrior(records),
+    }
+}
+
+fn render_csv(records: &[ExportRecord]) -> String {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    }
+
+    let mut csv =
+        String::from("kind,title,start,end,duration_seconds,duration,tags,project,description\n");
+    for record in records {
+        let sign = if record.duration_seconds < 0 { "-" } else { "" };
+        let row = [
+            match record.kind {
+                ExportRecordKind::Session => "session".to_owned(),
+                ExportRecordKind::Adjustment => "adjustment".to_owned(),
+            },
+            field(&record.title),
+            record.start.to_rfc3339_opts(SecondsFormat::Secs, false),
+            record
+                .end
+                .map(|end| end.to_rfc3339_opts(SecondsFormat::Secs, false))
+                .unwrap_or_default(),
+            record.duration_seconds.to_string(),
+            format!(
+                "{}{}",
+                sign,
+                format_duration(std::time::Duration::from_secs(
+                    record.duration_seconds.unsigned_abs()
+                ))
+            ),
+            field(&record.tags.join(" ")),
+            field(record.project.as_deref().unwrap_or_default()),
+            field(record.description.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Interval lines as found in timewarrior's data files. The title, tags and project all
+/// become timewarrior tags. Adjustments have no interval to show and are left out.
+fn render_timewarrior(records: &[ExportRecord]) -> String {
+    fn timestamp(date_time: DateTime<Local>) -> String {
+        date_time
+            .with_timezone(&Utc)
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string()
+    }
+    fn tag(value: &str) -> String {
+        if value.contains(|c: char| c.is_whitespace() || c == '"' || c == '#') {
+            format!("\"{}\"", value.replace('"', "\\\""))
+        } else {
+            value.to_owned()
+        }
+    }
+
+    let mut data = String::new();
+    for record in records
+        .iter()
+        .filter(|record| record.kind == ExportRecordKind::Session)
+    {
+        let mut tags = vec![tag(&record.title)];
+        tags.extend(record.tags.iter().map(|t| tag(t)));
+        tags.extend(
+            record
+                .project
+                .iter()
+                .map(|project| tag(&format!("@{}", project))),
+        );
+
+        data.push_str(&format!("inc {}", timestamp(record.start)));
+        if let Some(end) = record.end {
+            data.push_str(&format!(" - {}", timestamp(end)));
+        }
+        data.push_str(&format!(" # {}\n", tags.join(" ")));
+    }
+
+    data
+}
+
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Written to stdout from the command line when unset, to the home directory otherwise.
+    pub output: Option<PathBuf>,
+}
+
+impl ExportOptions {
+    /// File name used when no output is given, e.g. "timesheet-2024-05-01-2024-05-07.csv".
+    pub fn default_file_name(&self) -> String {
+        format!(
+            "timesheet-{}-{}.{}",
+            self.from,
+            self.to,
+            self.format.extension()
+        )
+    }
+}
+
+/// Writes the export to `path`, returning the number of exported records.
+pub fn export_to_file(timers: &[Timer], options: &ExportOptions, path: &Path) -> io::Result<usize> {
+    let records = collect_records(timers, options.from, options.to);
+    fs::write(path, render(options.format, &records))?;
+
+    Ok(records.len())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExportPopupInput {
+    Format,
+    From,
+    To,
+    Output,
+}
+
+impl ExportPopupInput {
+    fn next(&self) -> Self {
+        match self {
+            ExportPopupInput::Format => ExportPopupInput::From,
+            ExportPopupInput::From => ExportPopupInput::To,
+            ExportPopupInput::To => ExportPopupInput::Output,
+            ExportPopupInput::Output => ExportPopupInput::Format,
+        }
+    }
+
+    fn previous(&self) -> Self {
+        match self {
+            ExportPopupInput::Format => ExportPopupInput::Output,
+            ExportPopupInput::From => ExportPopupInput::Format,
+            ExportPopupInput::To => ExportPopupInput::From,
+            ExportPopupInput::Output => ExportPopupInput::To,
+        }
+    }
+}
+
+/// What the export popup asks the timer list to do after a key press.
+pub enum ExportAction {
+    None,
+    Close,
+    Export(ExportOptions),
+}
+
+/// Popup collecting the export options inside the Timers tab.
+pub struct ExportPopup {
+    pub format_input: TextInput,
+    pub from_input: TextInput,
+    pub to_input: TextInput,
+    pub output_input: TextInput,
+    pub focused_input: ExportPopupInput,
+    pub is_enabled: bool,
+    pub error: Option<String>,
+}
+
+impl Default for ExportPopup {
+    fn default() -> Self {
+        Self {
+            format_input: TextInput::default(),
+            from_input: TextInput::default(),
+            to_input: TextInput::default(),
+            output_input: TextInput::default(),
+            focused_input: ExportPopupInput::Format,
+            is_enabled: false,
+            error: None,
+        }
+    }
+}
+
+impl ExportPopup {
+    /// Opens with the current week selected.
+    pub fn open(&mut self) {
+        let today = Local::now().date_naive();
+        self.format_input = TextInput::with_value(String::from("csv"));
+        self.from_input = TextInput::with_value(start_of_week(today).to_string());
+        self.to_input = TextInput::with_value(String::from("today"));
+        self.output_input.clear();
+        self.focused_input = ExportPopupInput::Format;
+        self.error = None;
+        self.is_enabled = true;
+    }
+
+    fn options(&self) -> Result<ExportOptions, String> {
+        let today = Local::now().date_naive();
+        let from = parse_export_date(self.from_input.value(), today)?;
+        let to = parse_export_date(self.to_input.value(), today)?;
+        if to < from {
+            return Err("The range has to end after it starts".to_owned());
+        }
+
+        let output = self.output_input.value().trim();
+        Ok(ExportOptions {
+            format: ExportFormat::parse(self.format_input.value())?,
+            from,
+            to,
+            output: (!output.is_empty()).then(|| PathBuf::from(output)),
+        })
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> ExportAction {
+        let focused_input = match self.focused_input {
+            ExportPopupInput::Format => &mut self.format_input,
+            ExportPopupInput::From => &mut self.from_input,
+            ExportPopupInput::To => &mut self.to_input,
+            ExportPopupInput::Output => &mut self.output_input,
+        };
+        if focused_input.on_key(key, keymap) {
+            return ExportAction::None;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::NextField) => self.focused_input = self.focused_input.next(),
+            Some(Action::PreviousField) => self.focused_input = self.focused_input.previous(),
+            Some(Action::Cancel) => return ExportAction::Close,
+            Some(Action::Confirm) => match self.options() {
+                Ok(options) => return ExportAction::Export(options),
+                Err(error) => self.error = Some(error),
+            },
+            _ => {}
+        };
+
+        ExportAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        features::timers::{TimeAdjustment, TimerSession},
+        test_support::{date, time_on},
+    };
+
+    fn time(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        time_on(date(2024, 5, day), hour, minute)
+    }
+
+    fn adjustment(made_at: DateTime<Local>, offset_seconds: i64) -> TimeAdjustment {
+        TimeAdjustment {
+            made_at,
+            applies_to: None,
+            offset_seconds,
+            description: "Total changed".to_owned(),
+        }
+    }
+
+    #[test]
+    fn parses_export_dates() {
+        let today = date(2024, 5, 15);
+
+        assert_eq!(parse_export_date("today", today), Ok(today));
+        assert_eq!(parse_export_date("Yesterday", today), Ok(date(2024, 5, 14)));
+        assert_eq!(parse_export_date("-3d", today), Ok(date(2024, 5, 12)));
+        assert_eq!(parse_export_date("-2w", today), Ok(date(2024, 5, 1)));
+        assert_eq!(
+            parse_export_date("2024-04-30", today),
+            Ok(date(2024, 4, 30))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_export_dates() {
+        let today = date(2024, 5, 15);
+
+        assert!(parse_export_date("last week", today).is_err());
+        assert!(parse_export_date("-3m", today).is_err());
+        assert!(parse_export_date("+3d", today).is_err());
+        assert!(parse_export_date("-é", today).is_err());
+        assert!(parse_export_date("-3é", today).is_err());
+    }
+
+    #[test]
+    fn rejects_export_dates_too_far_back() {
+        let today = date(2024, 5, 15);
+
+        assert!(parse_export_date("-99999999999d", today).is_err());
+        assert!(parse_export_date("-3000000000000000000w", today).is_err());
+    }
+
+    #[test]
+    fn collects_sessions_cut_to_the_range() {
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![
+            TimerSession {
+                start: time(1, 23, 0),
+                end: Some(time(2, 1, 0)),
+            },
+            TimerSession {
+                start: time(3, 10, 0),
+                end: Some(time(3, 10, 30)),
+            },
+            TimerSession {
+                start: time(4, 9, 0),
+                end: Some(time(4, 10, 0)),
+            },
+        ];
+
+        let records = collect_records(&[timer], date(2024, 5, 2), date(2024, 5, 3));
+
+        let sessions: Vec<_> = records
+            .iter()
+            .map(|record| {
+                (
+                    record.kind,
+                    record.start,
+                    record.end,
+                    record.duration_seconds,
+                )
+            })
+            .collect();
+        assert_eq!(
+            sessions,
+            vec![
+                (
+                    ExportRecordKind::Session,
+                    time(2, 0, 0),
+                    Some(time(2, 1, 0)),
+                    3600
+                ),
+                (
+                    ExportRecordKind::Session,
+                    time(3, 10, 0),
+                    Some(time(3, 10, 30)),
+                    1800
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_adjustments_in_the_range() {
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![TimerSession {
+            start: time(2, 9, 0),
+            end: Some(time(2, 10, 0)),
+        }];
+        timer.adjustments = vec![
+            adjustment(time(1, 12, 0), 600),
+            adjustment(time(2, 12, 0), -900),
+            adjustment(time(2, 13, 0), 0),
+            adjustment(time(3, 12, 0), 1200),
+            TimeAdjustment {
+                applies_to: Some(date(2024, 5, 2)),
+                ..adjustment(time(3, 9, 0), 300)
+            },
+        ];
+
+        let records = collect_records(&[timer], date(2024, 5, 2), date(2024, 5, 2));
+
+        let records: Vec<_> = records
+            .iter()
+            .map(|record| (record.kind, record.start, record.duration_seconds))
+            .collect();
+        assert_eq!(
+            records,
+            vec![
+                (ExportRecordKind::Adjustment, time(2, 0, 0), 300),
+                (ExportRecordKind::Session, time(2, 9, 0), 3600),
+                (ExportRecordKind::Adjustment, time(2, 12, 0), -900),
+            ]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crossterm::event::KeyEvent;
+
+use crate::{
+    keymap::{Action, KeyScope, Keymap},
+    structures::text_input::TextInput,
+};
+
+/// Splits tag input like "work, #urgent later" into normalized tags.
+pub fn parse_tags(value: &str) -> Vec<String> {
+    let mut tags: Vec<String> = value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|tag| tag.trim_start_matches('#').to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.dedup();
+
+    tags
+}
+
+pub fn parse_project(value: &str) -> Option<String> {
+    let project = value.trim().trim_start_matches('@').trim();
+    if project.is_empty() {
+        None
+    } else {
+        Some(project.to_owned())
+    }
+}
+
+/// Filter query where `#tag` requires a tag, `@project` requires a project and any other
+/// word has to appear in the title. Tags and projects match by prefix so results narrow
+/// down while typing.
+#[derive(Clone, Default)]
+pub struct ItemFilter {
+    tags: Vec<String>,
+    project: Option<String>,
+    words: Vec<String>,
+}
+
+impl ItemFilter {
+    pub fn parse(query: &str) -> Self {
+        let mut filter = ItemFilter::default();
+
+        for token in query.split_whitespace() {
+            if let Some(tag) = token.strip_prefix('#') {
+                filter.tags.extend(parse_tags(tag));
+            } else if let Some(project) = token.strip_prefix('@') {
+                filter.project = parse_project(project).map(|project| project.to_lowercase());
+            } else {
+                filter.words.push(token.to_lowercase());
+            }
+        }
+
+        filter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.project.is_none() && self.words.is_empty()
+    }
+
+    pub fn matches(&self, title: &str, tags: &[String], project: Option<&str>) -> bool {
+        let title = title.to_lowercase();
+
+        self.tags
+            .iter()
+            .all(|filter_tag| tags.iter().any(|tag| tag.starts_with(filter_tag)))
+            && self.project.as_ref().is_none_or(|filter_project| {
+                project.is_some_and(|project| project.to_lowercase().starts_with(filter_project))
+            })
+            && self.words.iter().all(|word| title.contains(word))
+    }
+}
+
+/// Input line below a list that narrows it down while typing.
+#[derive(Default)]
+pub struct FilterBar {
+    pub input: TextInput,
+    pub is_enabled: bool,
+}
+
+impl FilterBar {
+    pub fn open(&mut self) {
+        self.is_enabled = true;
+    }
+
+    pub fn filter(&self) -> ItemFilter {
+        ItemFilter::parse(self.input.value())
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        let query = self.input.value().trim();
+        if query.is_empty() {
+            None
+        } else {
+            Some(query)
+        }
+    }
+
+    /// Confirming keeps the filter applied, cancelling clears it. Both close the bar.
+    /// Returns whether the key was used.
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
+        if self.input.on_key(key, keymap) {
+            return true;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::Confirm) => {
+                self.is_enabled = false;
+            }
+            Some(Action::Cancel) => {
+                self.input.clear();
+                self.is_enabled = false;
+            }
+            _ => return false,
+        };
+
+        true
+    }
+}
@@ -1,2 +1,6 @@
+pub mod export;
+pub mod filter;
+pub mod reports;
+pub mod search;
 pub mod tasks;
 pub mod timers;
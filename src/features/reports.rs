@@ -0,0 +1,110 @@
+use std::{cmp::Reverse, time::Duration};
+
+use chrono::{Days, Local, NaiveDate};
+
+use crate::{
+    features::{
+        tasks::Task,
+        timers::{start_of_day, Timer},
+    },
+    keymap::Action,
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportRange {
+    #[default]
+    Week,
+    Month,
+}
+
+impl ReportRange {
+    pub fn days(self) -> u64 {
+        match self {
+            ReportRange::Week => 7,
+            ReportRange::Month => 30,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportRange::Week => "last 7 days",
+            ReportRange::Month => "last 30 days",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            ReportRange::Week => ReportRange::Month,
+            ReportRange::Month => ReportRange::Week,
+        }
+    }
+
+    /// Every day in the range, oldest first and ending today.
+    pub fn dates(self, today: NaiveDate) -> Vec<NaiveDate> {
+        (0..self.days())
+            .rev()
+            .map(|days_ago| today - Days::new(days_ago))
+            .collect()
+    }
+}
+
+/// Active time of all timers on every day of the range.
+pub fn tracked_per_day(timers: &[Timer], dates: &[NaiveDate]) -> Vec<(NaiveDate, Duration)> {
+    dates
+        .iter()
+        .map(|date| {
+            let tracked = timers.iter().map(|timer| timer.time_active_on(*date)).sum();
+            (*date, tracked)
+        })
+        .collect()
+}
+
+/// Active time per timer since `from`, longest first. Timers without time are left out.
+pub fn tracked_per_timer(timers: &[Timer], from: NaiveDate) -> Vec<(&str, Duration)> {
+    let from = start_of_day(from);
+    let now = Local::now();
+    let mut tracked: Vec<(&str, Duration)> = timers
+        .iter()
+        .map(|timer| (timer.title.as_str(), timer.time_active_between(from, now)))
+        .filter(|(_, duration)| !duration.is_zero())
+        .collect();
+    tracked.sort_by_key(|(_, duration)| Reverse(*duration));
+
+    tracked
+}
+
+/// Number of tasks, subtasks included, completed on every day of the range.
+pub fn completed_per_day(tasks: &[Task], dates: &[NaiveDate]) -> Vec<(NaiveDate, u64)> {
+    fn collect_completion_dates(tasks: &[Task], completion_dates: &mut Vec<NaiveDate>) {
+        for task in tasks {
+            if let Some(completed_at) = task.completed_at.filter(|_| task.is_completed) {
+                completion_dates.push(completed_at.date_naive());
+            }
+            collect_completion_dates(&task.children, completion_dates);
+        }
+    }
+
+    let mut completion_dates = vec![];
+    collect_completion_dates(tasks, &mut completion_dates);
+
+    dates
+        .iter()
+        .map(|date| {
+            let completed = completion_dates.iter().filter(|d| *d == date).count();
+            (*date, completed as u64)
+        })
+        .collect()
+}
+
+#[derive(Default)]
+pub struct ReportState {
+    pub range: ReportRange,
+}
+
+impl ReportState {
+    pub fn on_action(&mut self, action: Action) {
+        if let Action::ToggleRange = action {
+            self.range = self.range.next();
+        }
+    }
+}
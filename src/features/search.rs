@@ -0,0 +1,152 @@
+use crossterm::event::KeyEvent;
+
+use crate::{
+    keymap::{Action, KeyScope, Keymap},
+    structures::text_input::TextInput,
+};
+
+/// Case-insensitive subsequence match returning the char indices of `text` that matched.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    let mut query_chars = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut matched_indices = vec![];
+
+    for (index, c) in text.chars().enumerate() {
+        match query_chars.peek() {
+            Some(query_char) if c.to_lowercase().eq(std::iter::once(*query_char)) => {
+                matched_indices.push(index);
+                query_chars.next();
+            }
+            Some(_) => {}
+            None => break,
+        };
+    }
+
+    if query_chars.peek().is_none() {
+        Some(matched_indices)
+    } else {
+        None
+    }
+}
+
+/// Finds the next row after `current` (or before it when `forward` is false) for which
+/// `is_match` holds, wrapping around the list.
+pub fn find_match(
+    row_count: usize,
+    current: Option<usize>,
+    forward: bool,
+    is_match: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    if row_count == 0 {
+        return None;
+    }
+    let start = current.unwrap_or(if forward { row_count - 1 } else { 0 });
+
+    (1..=row_count)
+        .map(|step| {
+            if forward {
+                (start + step) % row_count
+            } else {
+                (start + row_count - step) % row_count
+            }
+        })
+        .find(|row| is_match(*row))
+}
+
+/// Incremental search line. While open the list is narrowed to fuzzy matches, once
+/// confirmed the query stays around for jumping between matches in the full list.
+#[derive(Default)]
+pub struct SearchBar {
+    pub input: TextInput,
+    pub is_enabled: bool,
+    pub query: Option<String>,
+}
+
+impl SearchBar {
+    pub fn open(&mut self) {
+        self.input.clear();
+        self.query = None;
+        self.is_enabled = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.input.clear();
+        self.query = None;
+        self.is_enabled = false;
+    }
+
+    /// Query that is currently being typed, used to narrow the list.
+    pub fn typed_query(&self) -> Option<String> {
+        let query = self.input.value().trim();
+        if self.is_enabled && !query.is_empty() {
+            Some(query.to_owned())
+        } else {
+            None
+        }
+    }
+
+    /// Query to highlight, either still being typed or already confirmed.
+    pub fn highlighted_query(&self) -> Option<&str> {
+        if self.is_enabled {
+            Some(self.input.value().trim()).filter(|query| !query.is_empty())
+        } else {
+            self.query.as_deref()
+        }
+    }
+
+    /// Confirming keeps the query, cancelling drops it. Both close the search line.
+    /// Returns whether the key was used.
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
+        if self.input.on_key(key, keymap) {
+            return true;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::Confirm) => {
+                self.query = self.typed_query();
+                self.is_enabled = false;
+            }
+            Some(Action::Cancel) => {
+                self.clear();
+            }
+            _ => return false,
+        };
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_query_characters_in_order() {
+        assert_eq!(fuzzy_match("fb", "FooBar"), Some(vec![0, 3]));
+        assert_eq!(fuzzy_match("o b", "foo bar"), Some(vec![1, 4]));
+        assert_eq!(fuzzy_match("", "foo"), Some(vec![]));
+        assert_eq!(fuzzy_match("bf", "foobar"), None);
+        assert_eq!(fuzzy_match("foos", "foo"), None);
+    }
+
+    #[test]
+    fn finds_matches_wrapping_around() {
+        let is_match = |row: usize| row == 1 || row == 3;
+
+        assert_eq!(find_match(5, Some(1), true, is_match), Some(3));
+        assert_eq!(find_match(5, Some(3), true, is_match), Some(1));
+        assert_eq!(find_match(5, Some(1), false, is_match), Some(3));
+        assert_eq!(find_match(5, None, true, is_match), Some(1));
+        assert_eq!(find_match(5, None, false, is_match), Some(3));
+    }
+
+    #[test]
+    fn finds_the_current_row_when_it_is_the_only_match() {
+        assert_eq!(find_match(5, Some(2), true, |row| row == 2), Some(2));
+        assert_eq!(find_match(5, Some(2), true, |_| false), None);
+        assert_eq!(find_match(0, None, true, |_| true), None);
+    }
+}
@@ -1,118 +1,728 @@
-use crossterm::event::KeyCode;
+use std::cmp::Ordering;
 
-use crate::structures::stateful_list::StatefulList;
+use chrono::{DateTime, Days, Local, NaiveDate};
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+use tui::widgets::ListState;
 
-#[derive(Default)]
+use crate::{
+    features::{
+        filter::{parse_project, parse_tags, FilterBar},
+        search::{find_match, fuzzy_match, SearchBar},
+    },
+    keymap::{Action, KeyScope, Keymap},
+    structures::{
+        history::History,
+        stateful_tree::{StatefulTree, TreeItem},
+        text_input::TextInput,
+    },
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub fn raised(self) -> Self {
+        match self {
+            Priority::None => Priority::Low,
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High | Priority::Urgent => Priority::Urgent,
+        }
+    }
+
+    pub fn lowered(self) -> Self {
+        match self {
+            Priority::None | Priority::Low => Priority::None,
+            Priority::Medium => Priority::Low,
+            Priority::High => Priority::Medium,
+            Priority::Urgent => Priority::High,
+        }
+    }
+
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Priority::None => None,
+            Priority::Low => Some("low"),
+            Priority::Medium => Some("medium"),
+            Priority::High => Some("high"),
+            Priority::Urgent => Some("urgent"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TaskSortMode {
+    #[default]
+    Insertion,
+    Priority,
+    DueDate,
+    Completion,
+    Alphabetical,
+}
+
+impl TaskSortMode {
+    pub fn next(self) -> Self {
+        match self {
+            TaskSortMode::Insertion => TaskSortMode::Priority,
+            TaskSortMode::Priority => TaskSortMode::DueDate,
+            TaskSortMode::DueDate => TaskSortMode::Completion,
+            TaskSortMode::Completion => TaskSortMode::Alphabetical,
+            TaskSortMode::Alphabetical => TaskSortMode::Insertion,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TaskSortMode::Insertion => "insertion order",
+            TaskSortMode::Priority => "priority",
+            TaskSortMode::DueDate => "due date",
+            TaskSortMode::Completion => "completion",
+            TaskSortMode::Alphabetical => "title",
+        }
+    }
+
+    fn compare(self, a: &Task, b: &Task) -> Ordering {
+        let ordering = match self {
+            TaskSortMode::Insertion => Ordering::Equal,
+            TaskSortMode::Priority => b.priority.cmp(&a.priority),
+            // Tasks without a due date go last.
+            TaskSortMode::DueDate => match (a.due_date, b.due_date) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            TaskSortMode::Completion => a.is_completed.cmp(&b.is_completed),
+            TaskSortMode::Alphabetical => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+
+        ordering.then(a.id.cmp(&b.id))
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Task {
+    /// Stable identifier, also used to restore insertion order.
+    #[serde(default)]
+    pub id: u64,
     pub title: String,
     pub is_completed: bool,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Local>>,
+
+    #[serde(default)]
+    pub children: Vec<Task>,
+    #[serde(default)]
+    pub is_collapsed: bool,
+
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub priority: Priority,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// Where a due date lies relative to today, carrying the distance in days.
+pub enum DueStatus {
+    Overdue(i64),
+    Today,
+    Upcoming(i64),
 }
 
 impl Task {
-    pub fn default() -> Self {
+    pub fn new(id: u64, title: String) -> Self {
         Self {
-            title: String::from(""),
+            id,
+            title,
             is_completed: false,
+            completed_at: None,
+            children: vec![],
+            is_collapsed: false,
+            due_date: None,
+            priority: Priority::None,
+            tags: vec![],
+            project: None,
         }
     }
 
-    pub fn new(title: String) -> Self {
-        Self {
-            title,
-            is_completed: false,
+    pub fn due_status(&self, today: NaiveDate) -> Option<DueStatus> {
+        let days = (self.due_date? - today).num_days();
+
+        Some(match days {
+            0 => DueStatus::Today,
+            days if days < 0 => DueStatus::Overdue(-days),
+            days => DueStatus::Upcoming(days),
+        })
+    }
+
+    /// Completed and total count of direct subtasks.
+    pub fn completion_ratio(&self) -> (usize, usize) {
+        let completed = self
+            .children
+            .iter()
+            .filter(|child| child.is_completed)
+            .count();
+
+        (completed, self.children.len())
+    }
+}
+
+impl TreeItem for Task {
+    fn children(&self) -> &[Self] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Self> {
+        &mut self.children
+    }
+
+    fn is_collapsed(&self) -> bool {
+        self.is_collapsed
+    }
+
+    fn set_collapsed(&mut self, collapsed: bool) {
+        self.is_collapsed = collapsed;
+    }
+}
+
+/// Parses due date input such as "2024-05-01", "today", "tomorrow", "+3d" or "+2w".
+/// Empty input clears the due date.
+pub fn parse_due_date(value: &str, today: NaiveDate) -> Result<Option<NaiveDate>, String> {
+    let value = value.trim().to_lowercase();
+
+    match value.as_str() {
+        "" => return Ok(None),
+        "today" => return Ok(Some(today)),
+        "tomorrow" => return Ok(Some(today + Days::new(1))),
+        _ => {}
+    };
+
+    if let Some(offset) = value.strip_prefix('+') {
+        let (amount, days_per_unit) = match (offset.strip_suffix('d'), offset.strip_suffix('w')) {
+            (Some(amount), _) => (amount, Some(1)),
+            (_, Some(amount)) => (amount, Some(7)),
+            _ => (offset, None),
+        };
+        if let (Ok(amount), Some(days_per_unit)) = (amount.parse::<u64>(), days_per_unit) {
+            return amount
+                .checked_mul(days_per_unit)
+                .and_then(|days| today.checked_add_days(Days::new(days)))
+                .map(Some)
+                .ok_or_else(|| format!("Due date \"{}\" is too far away", value));
+        }
+    }
+
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .map(Some)
+        .map_err(|_| format!("Unrecognized due date \"{}\"", value))
+}
+
+/// What the task popup is going to do once confirmed.
+pub enum TaskPopupTarget {
+    NewTask,
+    NewSubtask(Vec<usize>),
+    EditTask(Vec<usize>),
+}
+
+#[derive(PartialEq, Eq)]
+pub enum TaskPopupInput {
+    Title,
+    DueDate,
+    Tags,
+    Project,
+}
+
+impl TaskPopupInput {
+    fn next(&self) -> Self {
+        match self {
+            TaskPopupInput::Title => TaskPopupInput::DueDate,
+            TaskPopupInput::DueDate => TaskPopupInput::Tags,
+            TaskPopupInput::Tags => TaskPopupInput::Project,
+            TaskPopupInput::Project => TaskPopupInput::Title,
+        }
+    }
+
+    fn previous(&self) -> Self {
+        match self {
+            TaskPopupInput::Title => TaskPopupInput::Project,
+            TaskPopupInput::DueDate => TaskPopupInput::Title,
+            TaskPopupInput::Tags => TaskPopupInput::DueDate,
+            TaskPopupInput::Project => TaskPopupInput::Tags,
         }
     }
 }
 
+/// Task changes other features react to, collected by the app after every key press.
+pub enum TaskEvent {
+    TrackTime { task_id: u64, title: String },
+    Completed(u64),
+}
+
+const HISTORY_LIMIT: usize = 100;
+
 pub struct TaskState {
-    pub tasks: StatefulList<Task>,
+    pub tasks: StatefulTree<Task>,
+    pub sort_mode: TaskSortMode,
+    next_task_id: u64,
+    history: History<(Vec<Task>, ListState, TaskSortMode)>,
+
+    pub title_input: TextInput,
+    pub due_date_input: TextInput,
+    pub tags_input: TextInput,
+    pub project_input: TextInput,
 
-    pub new_task: Task,
+    pub filter_bar: FilterBar,
+    pub search_bar: SearchBar,
 
     pub new_task_popup_enabled: bool,
+    pub popup_target: TaskPopupTarget,
+    pub focused_input: TaskPopupInput,
+    pub popup_error: Option<String>,
+
+    events: Vec<TaskEvent>,
+    pub is_dirty: bool,
 }
 
-// TODO: add editing for TASKS
 impl TaskState {
-    pub fn new() -> Self {
-        Self {
-            tasks: StatefulList::with_items(vec![]),
+    /// `next_task_id` is raised past the ids in `tasks` when it is behind them.
+    pub fn new(mut tasks: Vec<Task>, next_task_id: u64) -> Self {
+        // Tasks stored before ids existed get one assigned in their current order.
+        let mut next_task_id = next_task_id.max(max_task_id(&tasks) + 1);
+        assign_missing_task_ids(&mut tasks, &mut next_task_id);
+
+        let mut task_state = Self {
+            tasks: StatefulTree::with_items(tasks),
+            sort_mode: TaskSortMode::default(),
+            next_task_id,
+            history: History::new(HISTORY_LIMIT),
 
-            new_task: Task::default(),
+            title_input: TextInput::default(),
+            due_date_input: TextInput::default(),
+            tags_input: TextInput::default(),
+            project_input: TextInput::default(),
+
+            filter_bar: FilterBar::default(),
+            search_bar: SearchBar::default(),
 
             new_task_popup_enabled: false,
+            popup_target: TaskPopupTarget::NewTask,
+            focused_input: TaskPopupInput::Title,
+            popup_error: None,
+
+            events: vec![],
+            is_dirty: false,
+        };
+        task_state.sort_tasks();
+
+        task_state
+    }
+
+    pub fn next_task_id(&self) -> u64 {
+        self.next_task_id
+    }
+
+    fn sort_tasks(&mut self) {
+        let sort_mode = self.sort_mode;
+        self.tasks.sort_by(|a, b| sort_mode.compare(a, b));
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter_bar.filter();
+        let search_query = self.search_bar.typed_query();
+
+        self.tasks
+            .set_filter(if filter.is_empty() && search_query.is_none() {
+                None
+            } else {
+                Some(Box::new(move |task: &Task| {
+                    filter.matches(&task.title, &task.tags, task.project.as_deref())
+                        && search_query
+                            .as_ref()
+                            .is_none_or(|query| fuzzy_match(query, &task.title).is_some())
+                }))
+            });
+    }
+
+    /// Jumps to the next row matching the confirmed search query.
+    fn select_search_match(&mut self, forward: bool) {
+        if let Some(query) = &self.search_bar.query {
+            let rows = self.tasks.rows();
+            let row = find_match(rows.len(), self.tasks.state.selected(), forward, |row| {
+                self.tasks
+                    .get(&rows[row])
+                    .is_some_and(|task| fuzzy_match(query, &task.title).is_some())
+            });
+            if row.is_some() {
+                self.tasks.state.select(row);
+            }
+        }
+    }
+
+    /// Tasks, selection and sort mode as undo restores them.
+    fn snapshot(&self) -> (Vec<Task>, ListState, TaskSortMode) {
+        (
+            self.tasks.items.clone(),
+            self.tasks.state.clone(),
+            self.sort_mode,
+        )
+    }
+
+    fn record_history(&mut self) {
+        self.history.record(self.snapshot());
+    }
+
+    fn undo(&mut self) {
+        if let Some((items, state, sort_mode)) = self.history.undo(self.snapshot()) {
+            self.tasks.items = items;
+            self.tasks.state = state;
+            self.sort_mode = sort_mode;
+            self.is_dirty = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some((items, state, sort_mode)) = self.history.redo(self.snapshot()) {
+            self.tasks.items = items;
+            self.tasks.state = state;
+            self.sort_mode = sort_mode;
+            self.is_dirty = true;
+        }
+    }
+
+    fn cycle_sort_mode(&mut self) {
+        self.record_history();
+        self.sort_mode = self.sort_mode.next();
+        self.sort_tasks();
+        self.is_dirty = true;
+    }
+
+    fn change_selected_priority(&mut self, change: fn(Priority) -> Priority) {
+        if self.tasks.selected_path().is_none() {
+            return;
+        }
+
+        self.record_history();
+        if let Some(task) = self.tasks.selected_mut() {
+            task.priority = change(task.priority);
+            self.sort_tasks();
+            self.is_dirty = true;
         }
     }
 
     fn delete_selected_task(&mut self) {
-        self.tasks.delete_current();
+        if self.tasks.selected_path().is_some() {
+            self.record_history();
+            self.tasks.delete_current();
+            self.is_dirty = true;
+        }
     }
 
-    fn open_create_popup(&mut self) {
+    fn toggle_selected_task(&mut self) {
+        if self.tasks.selected_path().is_none() {
+            return;
+        }
+
+        self.record_history();
+        if let Some(task) = self.tasks.selected_mut() {
+            task.is_completed = !task.is_completed;
+            task.completed_at = task.is_completed.then(Local::now);
+            if task.is_completed {
+                self.events.push(TaskEvent::Completed(task.id));
+            }
+            self.sort_tasks();
+            self.is_dirty = true;
+        }
+    }
+
+    fn track_selected_task(&mut self) {
+        if let Some(task) = self.tasks.selected_mut() {
+            self.events.push(TaskEvent::TrackTime {
+                task_id: task.id,
+                title: task.title.clone(),
+            });
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<TaskEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn open_create_popup(&mut self, target: TaskPopupTarget) {
+        self.title_input.clear();
+        self.due_date_input.clear();
+        self.tags_input.clear();
+        self.project_input.clear();
+        self.popup_target = target;
         self.new_task_popup_enabled = true;
     }
 
+    fn open_edit_popup(&mut self) {
+        if let Some(path) = self.tasks.selected_path() {
+            if let Some(task) = self.tasks.get(&path) {
+                self.title_input = TextInput::with_value(task.title.to_owned());
+                self.due_date_input = TextInput::with_value(
+                    task.due_date
+                        .map(|due_date| due_date.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default(),
+                );
+                self.tags_input = TextInput::with_value(task.tags.join(", "));
+                self.project_input =
+                    TextInput::with_value(task.project.to_owned().unwrap_or_default());
+                self.popup_target = TaskPopupTarget::EditTask(path);
+                self.new_task_popup_enabled = true;
+            }
+        }
+    }
+
+    /// Whether the popup or an input bar is open.
+    pub fn is_editing(&self) -> bool {
+        self.new_task_popup_enabled || self.filter_bar.is_enabled || self.search_bar.is_enabled
+    }
+
+    /// Closes the popup without applying it, like Esc does.
+    pub fn close_popup(&mut self) {
+        if self.new_task_popup_enabled {
+            self.close_create_popup();
+        }
+    }
+
     fn close_create_popup(&mut self) {
         self.new_task_popup_enabled = false;
+        self.popup_target = TaskPopupTarget::NewTask;
+        self.focused_input = TaskPopupInput::Title;
+        self.popup_error = None;
     }
 
-    fn create_new_task(&mut self) {
-        let new_task = Task::new(self.new_task.title.to_owned());
-        self.tasks.items.push(new_task);
-        self.new_task = Task::default();
+    /// Applies the popup to the task list, keeping the popup open when the input is invalid.
+    fn confirm_popup(&mut self) -> Result<(), String> {
+        let title = self.title_input.value().to_owned();
+        let due_date = parse_due_date(self.due_date_input.value(), Local::now().date_naive())?;
+
+        let new_task = Task {
+            due_date,
+            tags: parse_tags(self.tags_input.value()),
+            project: parse_project(self.project_input.value()),
+            ..Task::new(self.next_task_id, title)
+        };
+
+        self.record_history();
+        match std::mem::replace(&mut self.popup_target, TaskPopupTarget::NewTask) {
+            TaskPopupTarget::NewTask => {
+                self.tasks.push(new_task);
+                self.next_task_id += 1;
+            }
+            TaskPopupTarget::NewSubtask(parent) => {
+                self.tasks.push_child(&parent, new_task);
+                self.next_task_id += 1;
+            }
+            TaskPopupTarget::EditTask(path) => {
+                if let Some(task) = self.tasks.get_mut(&path) {
+                    task.title = new_task.title;
+                    task.due_date = new_task.due_date;
+                    task.tags = new_task.tags;
+                    task.project = new_task.project;
+                }
+            }
+        };
+
+        self.sort_tasks();
+
+        self.is_dirty = true;
+
+        Ok(())
     }
 
-    pub fn on_keycode(&mut self, key: KeyCode) -> bool {
+    /// Handles keys meant for open popups, input bars and search results, returning whether
+    /// the key was used. Everything else goes through the keymap.
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
         if self.new_task_popup_enabled {
-            self.on_popup_keycode(key);
+            self.on_popup_key(key, keymap);
 
             return true;
         }
+        if self.filter_bar.is_enabled {
+            if self.filter_bar.on_key(key, keymap) {
+                self.apply_filter();
+            } else {
+                self.on_bar_key(key, keymap);
+            }
 
-        match key {
-            KeyCode::Up => self.tasks.previous(),
-            KeyCode::Down => self.tasks.next(),
-
-            KeyCode::Enter => {
-                let current_selection = self.tasks.state.selected();
-                if let Some(selection) = current_selection {
-                    self.tasks.items[selection].is_completed =
-                        !self.tasks.items[selection].is_completed;
-                }
+            return true;
+        }
+        if self.search_bar.is_enabled {
+            if self.search_bar.on_key(key, keymap) {
+                self.apply_filter();
+            } else {
+                self.on_bar_key(key, keymap);
             }
 
-            KeyCode::Char(c) => match c {
-                'd' => {
-                    self.delete_selected_task();
-                }
-                'n' => {
-                    self.open_create_popup();
-                    self.new_task = Task::default();
-                }
-                _ => {}
-            },
-            _ => {}
-        };
+            return true;
+        }
+        if self.search_bar.query.is_some() {
+            match keymap.action(KeyScope::Search, key) {
+                Some(Action::NextMatch) => self.select_search_match(true),
+                Some(Action::PreviousMatch) => self.select_search_match(false),
+                Some(Action::Cancel) => self.search_bar.clear(),
+                _ => return false,
+            };
+
+            return true;
+        }
 
         false
     }
 
-    fn on_popup_keycode(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Char(c) => {
-                self.new_task.title.push(c);
+    /// Keys the filter or search bar left unused can still move through the narrowed list.
+    fn on_bar_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        match keymap.action(KeyScope::Tasks, key) {
+            Some(Action::MoveUp) => self.tasks.previous(),
+            Some(Action::MoveDown) => self.tasks.next(),
+            _ => {}
+        };
+    }
+
+    /// Runs a keymap action, list movements repeat `count` times.
+    pub fn on_action(&mut self, action: Action, count: Option<usize>) {
+        match action {
+            Action::MoveUp => match count {
+                Some(count) => self.tasks.move_by(-(count as isize)),
+                None => self.tasks.previous(),
+            },
+            Action::MoveDown => match count {
+                Some(count) => self.tasks.move_by(count as isize),
+                None => self.tasks.next(),
+            },
+            // A count selects the row with that number, like "5G" in vim
+            Action::MoveTop | Action::MoveBottom if count.is_some() => {
+                self.tasks.select_row(count.unwrap_or(1) - 1)
             }
-            KeyCode::Backspace => {
-                self.new_task.title.pop();
+            Action::MoveTop => self.tasks.select_row(0),
+            Action::MoveBottom => self.tasks.select_row(usize::MAX),
+            Action::Toggle => self.toggle_selected_task(),
+            Action::New => self.open_create_popup(TaskPopupTarget::NewTask),
+            Action::NewSubtask => {
+                if let Some(path) = self.tasks.selected_path() {
+                    self.open_create_popup(TaskPopupTarget::NewSubtask(path));
+                }
             }
-            KeyCode::Esc => {
-                self.close_create_popup();
+            Action::Edit => self.open_edit_popup(),
+            Action::Delete => self.delete_selected_task(),
+            Action::RaisePriority => self.change_selected_priority(Priority::raised),
+            Action::LowerPriority => self.change_selected_priority(Priority::lowered),
+            Action::ToggleCollapse => {
+                self.tasks.toggle_collapsed();
+                self.is_dirty = true;
             }
-            KeyCode::Enter => {
-                self.create_new_task();
+            Action::SelectParent => self.tasks.select_parent(),
+            Action::CycleSort => self.cycle_sort_mode(),
+            Action::TrackTime => self.track_selected_task(),
+            Action::Filter => self.filter_bar.open(),
+            Action::Search => self.search_bar.open(),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            _ => {}
+        };
+    }
+
+    fn on_popup_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        let focused_input = match self.focused_input {
+            TaskPopupInput::Title => &mut self.title_input,
+            TaskPopupInput::DueDate => &mut self.due_date_input,
+            TaskPopupInput::Tags => &mut self.tags_input,
+            TaskPopupInput::Project => &mut self.project_input,
+        };
+        if focused_input.on_key(key, keymap) {
+            return;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::NextField) => {
+                self.focused_input = self.focused_input.next();
+            }
+            Some(Action::PreviousField) => {
+                self.focused_input = self.focused_input.previous();
+            }
+            Some(Action::Cancel) => {
                 self.close_create_popup();
             }
+            Some(Action::Confirm) => match self.confirm_popup() {
+                Ok(()) => self.close_create_popup(),
+                Err(error) => self.popup_error = Some(error),
+            },
             _ => {}
         };
     }
 }
+
+fn max_task_id(tasks: &[Task]) -> u64 {
+    tasks
+        .iter()
+        .map(|task| task.id.max(max_task_id(&task.children)))
+        .max()
+        .unwrap_or(0)
+}
+
+fn assign_missing_task_ids(tasks: &mut [Task], next_task_id: &mut u64) {
+    for task in tasks {
+        if task.id == 0 {
+            task.id = *next_task_id;
+            *next_task_id += 1;
+        }
+        assign_missing_task_ids(&mut task.children, next_task_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::date;
+
+    #[test]
+    fn parses_due_dates() {
+        let today = date(2024, 5, 1);
+
+        assert_eq!(parse_due_date("", today), Ok(None));
+        assert_eq!(parse_due_date(" Today ", today), Ok(Some(today)));
+        assert_eq!(
+            parse_due_date("tomorrow", today),
+            Ok(Some(date(2024, 5, 2)))
+        );
+        assert_eq!(parse_due_date("+3d", today), Ok(Some(date(2024, 5, 4))));
+        assert_eq!(parse_due_date("+2w", today), Ok(Some(date(2024, 5, 15))));
+        assert_eq!(
+            parse_due_date("2024-12-24", today),
+            Ok(Some(date(2024, 12, 24)))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_due_dates() {
+        let today = date(2024, 5, 1);
+
+        assert!(parse_due_date("someday", today).is_err());
+        assert!(parse_due_date("+3m", today).is_err());
+        assert!(parse_due_date("2024-13-01", today).is_err());
+        assert!(parse_due_date("+é", today).is_err());
+        assert!(parse_due_date("+3é", today).is_err());
+    }
+
+    #[test]
+    fn rejects_due_dates_too_far_away() {
+        let today = date(2024, 5, 1);
+
+        assert!(parse_due_date("+99999999999d", today).is_err());
+        assert!(parse_due_date("+3000000000000000000w", today).is_err());
+    }
+}
@@ -1,141 +1,1929 @@
 use std::{
-    ops::AddAssign,
-    time::{Duration, Instant},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+    time::Duration,
 };
 
-use chrono::{DateTime, Local};
-use crossterm::event::KeyCode;
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use crossterm::event::KeyEvent;
+use serde::{Deserialize, Serialize};
+use tui::widgets::ListState;
 
-use crate::structures::stateful_list::StatefulList;
+use crate::{
+    config::{PomodoroConfig, TimersConfig},
+    features::{
+        export::{export_to_file, parse_export_date, ExportAction, ExportPopup},
+        filter::{parse_project, parse_tags, FilterBar},
+        search::{find_match, fuzzy_match, SearchBar},
+    },
+    keymap::{Action, KeyScope, Keymap},
+    structures::{history::History, stateful_list::StatefulList, text_input::TextInput},
+};
+
+/// A single run of a timer. Sessions without an end are still running.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimerSession {
+    pub start: DateTime<Local>,
+    pub end: Option<DateTime<Local>>,
+}
+
+impl TimerSession {
+    pub fn starting_now() -> Self {
+        Self {
+            start: Local::now(),
+            end: None,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        let end = self.end.unwrap_or_else(Local::now);
+        (end - self.start).to_std().unwrap_or_default()
+    }
+}
+
+/// Part of a session that falls on a single day.
+pub struct SessionPart {
+    /// Index of the session in the timer.
+    pub index: usize,
+    pub start: DateTime<Local>,
+    /// Unset while the session is still running.
+    pub end: Option<DateTime<Local>>,
+}
+
+impl SessionPart {
+    pub fn duration(&self) -> Duration {
+        let end = self.end.unwrap_or_else(Local::now);
+        (end - self.start).to_std().unwrap_or_default()
+    }
+}
+
+/// Everything a timer tracked on one day.
+pub struct TimerDay {
+    pub date: NaiveDate,
+    pub sessions: Vec<SessionPart>,
+    /// Sum of the adjustments made for the day.
+    pub offset_seconds: i64,
+}
+
+impl TimerDay {
+    fn new(date: NaiveDate) -> Self {
+        Self {
+            date,
+            sessions: vec![],
+            offset_seconds: 0,
+        }
+    }
+}
+
+/// Monday of the week `date` falls in.
+pub fn start_of_week(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+/// Local midnight at the start of `date`.
+pub fn start_of_day(date: NaiveDate) -> DateTime<Local> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    midnight
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PomodoroPhase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl PomodoroPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            PomodoroPhase::Work => "work",
+            PomodoroPhase::ShortBreak => "short break",
+            PomodoroPhase::LongBreak => "long break",
+        }
+    }
+
+    pub fn length(self, config: &PomodoroConfig) -> Duration {
+        let minutes = match self {
+            PomodoroPhase::Work => config.work_minutes,
+            PomodoroPhase::ShortBreak => config.short_break_minutes,
+            PomodoroPhase::LongBreak => config.long_break_minutes,
+        };
+
+        Duration::from_secs(minutes * 60)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Pomodoro {
+    pub phase: PomodoroPhase,
+    /// Active time after this moment counts towards the current phase.
+    pub phase_started_at: DateTime<Local>,
+    pub completed_in_cycle: u32,
+    pub completed_per_day: BTreeMap<NaiveDate, u32>,
+    /// Set when a work phase paused the running timer for a break, the timer starts again
+    /// once the break is over.
+    #[serde(default)]
+    pub resumes_after_break: bool,
+}
+
+impl Pomodoro {
+    pub fn new() -> Self {
+        Self {
+            phase: PomodoroPhase::Work,
+            phase_started_at: Local::now(),
+            completed_in_cycle: 0,
+            completed_per_day: BTreeMap::new(),
+            resumes_after_break: false,
+        }
+    }
+
+    pub fn is_break(&self) -> bool {
+        self.phase != PomodoroPhase::Work
+    }
+
+    pub fn completed_on(&self, date: NaiveDate) -> u32 {
+        self.completed_per_day.get(&date).copied().unwrap_or(0)
+    }
+
+    fn finish_phase(&mut self, finished_at: DateTime<Local>, config: &PomodoroConfig) {
+        self.phase = match self.phase {
+            PomodoroPhase::Work => {
+                self.completed_in_cycle += 1;
+                *self
+                    .completed_per_day
+                    .entry(finished_at.date_naive())
+                    .or_default() += 1;
 
+                if self
+                    .completed_in_cycle
+                    .is_multiple_of(config.long_break_interval.max(1))
+                {
+                    PomodoroPhase::LongBreak
+                } else {
+                    PomodoroPhase::ShortBreak
+                }
+            }
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => PomodoroPhase::Work,
+        };
+        self.phase_started_at = finished_at;
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Countdown {
+    pub target: Duration,
+    /// Active time after this moment counts towards the target.
+    pub counting_since: DateTime<Local>,
+    /// Set once the target was reached, cleared again when acknowledged.
+    pub expired_at: Option<DateTime<Local>>,
+}
+
+impl Countdown {
+    pub fn new(target: Duration) -> Self {
+        Self {
+            target,
+            counting_since: Local::now(),
+            expired_at: None,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub enum TimerKind {
+    #[default]
+    Stopwatch,
+    Pomodoro(Pomodoro),
+    Countdown(Countdown),
+}
+
+/// Formats a duration as HH:MM:SS.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// Parses duration input such as "25m", "1h30m", "1h 30m" or "90s". A bare number counts
+/// minutes. Empty input means no duration.
+pub fn parse_duration(value: &str) -> Result<Option<Duration>, String> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match parse_seconds(&value)? {
+        0 => Err("Duration has to be longer than zero".to_owned()),
+        seconds => Ok(Some(Duration::from_secs(seconds))),
+    }
+}
+
+fn parse_seconds(value: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid duration \"{}\", use e.g. 25m or 1h30m", value);
+    let too_long = || format!("Duration \"{}\" is too long", value);
+
+    let mut seconds: u64 = 0;
+    let mut amount = String::new();
+    for c in value.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            amount.push(c);
+            continue;
+        }
+
+        let seconds_per_unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let amount_value: u64 = amount.parse().map_err(|_| invalid())?;
+        seconds = amount_value
+            .checked_mul(seconds_per_unit)
+            .and_then(|unit_seconds| seconds.checked_add(unit_seconds))
+            .ok_or_else(too_long)?;
+        amount.clear();
+    }
+    if !amount.is_empty() {
+        let minutes: u64 = amount.parse().map_err(|_| invalid())?;
+        seconds = minutes
+            .checked_mul(60)
+            .and_then(|unit_seconds| seconds.checked_add(unit_seconds))
+            .ok_or_else(too_long)?;
+    }
+
+    Ok(seconds)
+}
+
+/// Parses a correction of a timer total: "+30m" or "30m" adds, "-15m" subtracts and "=2h"
+/// sets the exact total. Returns the signed change in seconds, empty input means none.
+/// The change counts towards a single day, subtracting more than `day_total` (the time on
+/// that day) only brings the day down to zero.
+pub fn parse_adjustment(
+    value: &str,
+    current_total: Duration,
+    day_total: Duration,
+) -> Result<Option<i64>, String> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let too_long = || format!("Duration \"{}\" is too long", value);
+    let seconds = |value: &str| {
+        parse_seconds(value).and_then(|seconds| i64::try_from(seconds).map_err(|_| too_long()))
+    };
+    let current_seconds = i64::try_from(current_total.as_secs()).map_err(|_| too_long())?;
+    let day_seconds = i64::try_from(day_total.as_secs()).map_err(|_| too_long())?;
+    let offset_seconds = if let Some(total) = value.strip_prefix('=') {
+        let offset_seconds = seconds(total)? - current_seconds;
+        if offset_seconds < -day_seconds {
+            return Err(format!(
+                "Only {} was tracked on that day, pick the day to take the time off",
+                format_duration(day_total)
+            ));
+        }
+        offset_seconds
+    } else if let Some(subtracted) = value.strip_prefix('-') {
+        // The rest would be taken off time tracked on other days
+        (-seconds(subtracted)?).max(-day_seconds)
+    } else {
+        let added = seconds(value.trim_start_matches('+'))?;
+        current_seconds.checked_add(added).ok_or_else(too_long)?;
+        added
+    };
+
+    Ok(Some(offset_seconds))
+}
+
+pub const SESSION_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Parses "2024-05-01 18:30", or just "18:30" on `date`. Empty input means no time.
+pub fn parse_session_time(value: &str, date: NaiveDate) -> Result<Option<DateTime<Local>>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let date_time = NaiveDateTime::parse_from_str(value, SESSION_TIME_FORMAT)
+        .or_else(|_| NaiveTime::parse_from_str(value, "%H:%M").map(|time| date.and_time(time)))
+        .map_err(|_| format!("Invalid time \"{}\", use e.g. 18:30", value))?;
+
+    date_time
+        .and_local_timezone(Local)
+        .earliest()
+        .map(Some)
+        .ok_or_else(|| format!("\"{}\" does not exist in the local time zone", value))
+}
+
+/// Signed `offset_seconds` applied to `duration`, never going below zero.
+fn with_offset(duration: Duration, offset_seconds: i64) -> Duration {
+    let offset = Duration::from_secs(offset_seconds.unsigned_abs());
+    if offset_seconds >= 0 {
+        duration + offset
+    } else {
+        duration.saturating_sub(offset)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BudgetPeriod {
+    Daily,
+    Weekly,
+}
+
+impl BudgetPeriod {
+    pub fn label(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "today",
+            BudgetPeriod::Weekly => "this week",
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "day",
+            BudgetPeriod::Weekly => "week",
+        }
+    }
+}
+
+/// Cap on the time a timer should take per day or week.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Budget {
+    pub period: BudgetPeriod,
+    pub limit: Duration,
+}
+
+impl Budget {
+    /// Budget in the same shape `parse_budget` accepts, e.g. "2h/day".
+    pub fn to_input(self) -> String {
+        let minutes = self.limit.as_secs() / 60;
+        let limit = match (minutes / 60, minutes % 60) {
+            (0, minutes) => format!("{}m", minutes),
+            (hours, 0) => format!("{}h", hours),
+            (hours, minutes) => format!("{}h{}m", hours, minutes),
+        };
+
+        format!("{}/{}", limit, self.period.suffix())
+    }
+}
+
+/// Parses budget input such as "2h/day" or "5h30m/week". Empty input means no budget.
+pub fn parse_budget(value: &str) -> Result<Option<Budget>, String> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let (limit, period) = value
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid budget \"{}\", use e.g. 2h/day or 5h/week", value))?;
+    let period = match period.trim() {
+        "d" | "day" => BudgetPeriod::Daily,
+        "w" | "week" => BudgetPeriod::Weekly,
+        period => {
+            return Err(format!(
+                "Unknown budget period \"{}\", use day or week",
+                period
+            ))
+        }
+    };
+    let limit = parse_duration(limit)?.ok_or_else(|| "Budget needs a duration".to_owned())?;
+
+    Ok(Some(Budget { period, limit }))
+}
+
+/// Manual correction of a timer, kept so totals stay auditable. Session edits change the
+/// sessions themselves and are recorded without an offset.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimeAdjustment {
+    pub made_at: DateTime<Local>,
+    /// Day the change counts towards, the day it was made when unset.
+    #[serde(default)]
+    pub applies_to: Option<NaiveDate>,
+    pub offset_seconds: i64,
+    pub description: String,
+}
+
+impl TimeAdjustment {
+    /// Moment the change counts towards: when it was made, or the start of an earlier day
+    /// it was made for.
+    pub fn counts_at(&self) -> DateTime<Local> {
+        match self.applies_to {
+            Some(date) if date != self.made_at.date_naive() => start_of_day(date),
+            _ => self.made_at,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Timer {
     pub title: String,
-    pub is_active: bool,
-    pub time_active: Duration,
+    pub sessions: Vec<TimerSession>,
     pub time_created: DateTime<Local>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+
+    #[serde(default)]
+    pub kind: TimerKind,
+
+    /// Task this timer tracks time for.
+    #[serde(default)]
+    pub task_id: Option<u64>,
+
+    #[serde(default)]
+    pub adjustments: Vec<TimeAdjustment>,
+
+    #[serde(default)]
+    pub budget: Option<Budget>,
 }
 
 impl Timer {
-    pub fn default() -> Self {
+    pub fn new(title: String) -> Self {
         Self {
-            title: String::from(""),
+            title,
             time_created: Local::now(),
-            is_active: false,
-            time_active: Duration::from_millis(0),
+            sessions: vec![],
+            tags: vec![],
+            project: None,
+            kind: TimerKind::Stopwatch,
+            task_id: None,
+            adjustments: vec![],
+            budget: None,
         }
     }
 
-    pub fn new(title: String) -> Self {
-        Self {
-            title,
-            time_created: Local::now(),
-            is_active: false,
-            time_active: Duration::from_millis(0),
+    /// Time counting against the budget in its current period.
+    pub fn budget_used(&self) -> Option<Duration> {
+        let budget = self.budget?;
+        let now = Local::now();
+        let period_start = match budget.period {
+            BudgetPeriod::Daily => now.date_naive(),
+            BudgetPeriod::Weekly => start_of_week(now.date_naive()),
+        };
+
+        Some(self.time_active_between(start_of_day(period_start), now))
+    }
+
+    pub fn is_over_budget(&self) -> bool {
+        self.budget
+            .zip(self.budget_used())
+            .is_some_and(|(budget, used)| used > budget.limit)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.sessions
+            .last()
+            .is_some_and(|session| session.end.is_none())
+    }
+
+    pub fn time_active(&self) -> Duration {
+        let offset_seconds = self.adjustments.iter().map(|a| a.offset_seconds).sum();
+        with_offset(
+            self.sessions.iter().map(TimerSession::duration).sum(),
+            offset_seconds,
+        )
+    }
+
+    /// Session time since `since`, manual adjustments are not taken into account.
+    pub fn time_active_since(&self, since: DateTime<Local>) -> Duration {
+        self.session_time_between(since, Local::now())
+    }
+
+    /// Active time that falls between `from` and `to`, splitting sessions at the edges.
+    /// Adjustments count towards the day they were made for.
+    pub fn time_active_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Duration {
+        let offset_seconds = self
+            .adjustments
+            .iter()
+            .filter(|adjustment| (from..to).contains(&adjustment.counts_at()))
+            .map(|adjustment| adjustment.offset_seconds)
+            .sum();
+
+        with_offset(self.session_time_between(from, to), offset_seconds)
+    }
+
+    fn session_time_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> Duration {
+        let now = Local::now();
+        self.sessions
+            .iter()
+            .filter_map(|session| {
+                let start = session.start.max(from);
+                let end = session.end.unwrap_or(now).min(to);
+                (end - start).to_std().ok()
+            })
+            .sum()
+    }
+
+    /// Adds (or with a negative offset subtracts) time from the total on `date`.
+    pub fn adjust_total(&mut self, offset_seconds: i64, date: NaiveDate, description: String) {
+        self.adjustments.push(TimeAdjustment {
+            made_at: Local::now(),
+            applies_to: Some(date),
+            offset_seconds,
+            description,
+        });
+    }
+
+    /// Cuts `from..to` out of the sessions, splitting a session that spans it.
+    pub fn remove_interval(&mut self, from: DateTime<Local>, to: DateTime<Local>) {
+        let mut sessions = vec![];
+        for session in self.sessions.drain(..) {
+            if session.start < from {
+                sessions.push(TimerSession {
+                    start: session.start,
+                    end: Some(session.end.map_or(from, |end| end.min(from))),
+                });
+            }
+            if session.end.is_none_or(|end| end > to) {
+                sessions.push(TimerSession {
+                    start: session.start.max(to),
+                    end: session.end,
+                });
+            }
+        }
+
+        self.sessions = sessions;
+    }
+
+    /// Adds a finished session, keeping sessions ordered by their start.
+    pub fn insert_session(&mut self, start: DateTime<Local>, end: DateTime<Local>) {
+        let index = self
+            .sessions
+            .partition_point(|session| session.start <= start);
+        self.sessions.insert(
+            index,
+            TimerSession {
+                start,
+                end: Some(end),
+            },
+        );
+    }
+
+    /// Moves the start and end of a session. Only the last session may be left running and
+    /// sessions may not overlap, as overlapping time would be counted twice.
+    pub fn edit_session(
+        &mut self,
+        index: usize,
+        start: DateTime<Local>,
+        end: Option<DateTime<Local>>,
+    ) -> Result<(), String> {
+        if index >= self.sessions.len() {
+            return Err(format!("Session #{} does not exist", index + 1));
+        }
+        if end.is_some_and(|end| end < start) {
+            return Err("Session end has to be after its start".to_owned());
+        }
+        if end.is_none() && index + 1 != self.sessions.len() {
+            return Err("Only the last session can be left running".to_owned());
+        }
+        let now = Local::now();
+        if start > now {
+            return Err("Session start can't be in the future".to_owned());
+        }
+        if end.is_some_and(|end| end > now) {
+            return Err("Session end can't be in the future".to_owned());
+        }
+        let overlap = |other: usize| format!("Session would overlap session #{}", other + 1);
+        if index > 0
+            && self.sessions[index - 1]
+                .end
+                .is_none_or(|previous_end| previous_end > start)
+        {
+            return Err(overlap(index - 1));
+        }
+        if let Some(next) = self.sessions.get(index + 1) {
+            if end.is_none_or(|end| end > next.start) {
+                return Err(overlap(index + 1));
+            }
+        }
+
+        let session = &mut self.sessions[index];
+        let format_end = |end: Option<DateTime<Local>>| match end {
+            Some(end) => end.format(SESSION_TIME_FORMAT).to_string(),
+            None => "running".to_owned(),
+        };
+        let description = format!(
+            "Session #{} changed from {} - {} to {} - {}",
+            index + 1,
+            session.start.format(SESSION_TIME_FORMAT),
+            format_end(session.end),
+            start.format(SESSION_TIME_FORMAT),
+            format_end(end)
+        );
+        session.start = start;
+        session.end = end;
+        self.adjustments.push(TimeAdjustment {
+            made_at: Local::now(),
+            applies_to: None,
+            offset_seconds: 0,
+            description,
+        });
+
+        Ok(())
+    }
+
+    /// Active time on the given day, counting only the part of a session on that day.
+    pub fn time_active_on(&self, date: NaiveDate) -> Duration {
+        self.time_active_between(start_of_day(date), start_of_day(date + Days::new(1)))
+    }
+
+    /// Session parts and adjustments grouped by day, most recent day first. A session
+    /// crossing midnight has a part on every day it covers, matching `time_active_on`.
+    pub fn days(&self) -> Vec<TimerDay> {
+        let now = Local::now();
+        let mut days: BTreeMap<NaiveDate, TimerDay> = BTreeMap::new();
+
+        for (index, session) in self.sessions.iter().enumerate() {
+            let mut date = session.start.date_naive();
+            let last_date = session.end.unwrap_or(now).date_naive();
+            loop {
+                let start = session.start.max(start_of_day(date));
+                let end = session
+                    .end
+                    .map(|end| end.min(start_of_day(date + Days::new(1))));
+                // Skips the empty part of a session that ended exactly at midnight
+                if date == session.start.date_naive() || end.is_none_or(|end| end > start) {
+                    days.entry(date)
+                        .or_insert_with(|| TimerDay::new(date))
+                        .sessions
+                        .push(SessionPart { index, start, end });
+                }
+                if date >= last_date {
+                    break;
+                }
+                date = date + Days::new(1);
+            }
+        }
+        for adjustment in self.adjustments.iter() {
+            let date = adjustment.counts_at().date_naive();
+            days.entry(date)
+                .or_insert_with(|| TimerDay::new(date))
+                .offset_seconds += adjustment.offset_seconds;
+        }
+
+        days.into_values()
+            .rev()
+            .filter(|day| !day.sessions.is_empty() || day.offset_seconds != 0)
+            .collect()
+    }
+
+    /// Moment at which the timer has been active for `duration` after `since`, if reached yet.
+    pub fn moment_active_for(
+        &self,
+        since: DateTime<Local>,
+        duration: Duration,
+    ) -> Option<DateTime<Local>> {
+        let now = Local::now();
+        let mut remaining = chrono::Duration::from_std(duration).ok()?;
+
+        for session in &self.sessions {
+            let start = session.start.max(since);
+            let end = session.end.unwrap_or(now);
+            if end <= start {
+                continue;
+            }
+            if end - start >= remaining {
+                return Some(start + remaining);
+            }
+            remaining -= end - start;
         }
+
+        None
     }
+
+    /// Time left in the current pomodoro phase.
+    pub fn pomodoro_remaining(&self, config: &PomodoroConfig) -> Option<Duration> {
+        let TimerKind::Pomodoro(pomodoro) = &self.kind else {
+            return None;
+        };
+        let elapsed = if pomodoro.is_break() {
+            (Local::now() - pomodoro.phase_started_at)
+                .to_std()
+                .unwrap_or_default()
+        } else {
+            self.time_active_since(pomodoro.phase_started_at)
+        };
+
+        Some(pomodoro.phase.length(config).saturating_sub(elapsed))
+    }
+
+    /// Whether the timer is paused for a pomodoro break and starts again after it.
+    pub fn resumes_after_break(&self) -> bool {
+        matches!(&self.kind, TimerKind::Pomodoro(pomodoro) if pomodoro.resumes_after_break)
+    }
+
+    /// Time left until the countdown expires.
+    pub fn countdown_remaining(&self) -> Option<Duration> {
+        match &self.kind {
+            TimerKind::Countdown(countdown) => Some(
+                countdown
+                    .target
+                    .saturating_sub(self.time_active_since(countdown.counting_since)),
+            ),
+            TimerKind::Stopwatch | TimerKind::Pomodoro(_) => None,
+        }
+    }
+
+    /// Expired countdowns stay marked until acknowledged.
+    pub fn is_expired(&self) -> bool {
+        matches!(&self.kind, TimerKind::Countdown(countdown) if countdown.expired_at.is_some())
+    }
+
+    /// Clears the expiry mark and rewinds the countdown to its full target.
+    pub fn acknowledge_expiry(&mut self) {
+        if let TimerKind::Countdown(countdown) = &mut self.kind {
+            countdown.counting_since = Local::now();
+            countdown.expired_at = None;
+        }
+    }
+
+    /// Stops a countdown that reached its target, returning an alert if it just expired.
+    fn expire_countdown(&mut self) -> Option<String> {
+        let TimerKind::Countdown(countdown) = &self.kind else {
+            return None;
+        };
+        if countdown.expired_at.is_some() {
+            return None;
+        }
+        let expired_at = self.moment_active_for(countdown.counting_since, countdown.target)?;
+
+        for session in self.sessions.iter_mut().filter(|s| s.end.is_none()) {
+            session.end = Some(expired_at.max(session.start));
+        }
+        if let TimerKind::Countdown(countdown) = &mut self.kind {
+            countdown.expired_at = Some(expired_at);
+        }
+
+        Some(format!("\"{}\": countdown finished", self.title))
+    }
+
+    /// Moves past every pomodoro phase that has run out, returning an alert if any did.
+    /// Breaks are not tracked time: the timer is paused for them and they run on the clock.
+    fn advance_pomodoro(&mut self, config: &PomodoroConfig) -> Option<String> {
+        let mut finished_phase = None;
+
+        while let TimerKind::Pomodoro(pomodoro) = &self.kind {
+            let phase = pomodoro.phase;
+            let finished_at = if pomodoro.is_break() {
+                chrono::Duration::from_std(phase.length(config))
+                    .ok()
+                    .and_then(|length| pomodoro.phase_started_at.checked_add_signed(length))
+                    .filter(|end| *end <= Local::now())
+            } else {
+                self.moment_active_for(pomodoro.phase_started_at, phase.length(config))
+            };
+            let Some(finished_at) = finished_at else {
+                break;
+            };
+
+            let was_active = self.is_active();
+            if phase == PomodoroPhase::Work {
+                self.stop_at(finished_at);
+            }
+            if let TimerKind::Pomodoro(pomodoro) = &mut self.kind {
+                pomodoro.finish_phase(finished_at, config);
+                if phase == PomodoroPhase::Work {
+                    pomodoro.resumes_after_break = was_active;
+                } else if std::mem::take(&mut pomodoro.resumes_after_break) {
+                    self.sessions.push(TimerSession {
+                        start: finished_at,
+                        end: None,
+                    });
+                }
+            }
+            finished_phase = Some(phase);
+        }
+
+        match (&self.kind, finished_phase) {
+            (TimerKind::Pomodoro(pomodoro), Some(finished_phase)) => Some(format!(
+                "\"{}\": {} finished, {} started",
+                self.title,
+                finished_phase.label(),
+                pomodoro.phase.label()
+            )),
+            _ => None,
+        }
+    }
+
+    /// Starting a pomodoro during a break skips the rest of it.
+    pub fn start(&mut self) {
+        if self.is_active() {
+            return;
+        }
+        if let TimerKind::Pomodoro(pomodoro) = &mut self.kind {
+            if pomodoro.is_break() {
+                pomodoro.phase = PomodoroPhase::Work;
+                pomodoro.phase_started_at = Local::now();
+            }
+            pomodoro.resumes_after_break = false;
+        }
+        self.sessions.push(TimerSession::starting_now());
+    }
+
+    /// Stopping a pomodoro during a break keeps it from starting again afterwards.
+    pub fn stop(&mut self) {
+        self.stop_at(Local::now());
+        if let TimerKind::Pomodoro(pomodoro) = &mut self.kind {
+            pomodoro.resumes_after_break = false;
+        }
+    }
+
+    fn stop_at(&mut self, end: DateTime<Local>) {
+        if let Some(session) = self.sessions.last_mut() {
+            if session.end.is_none() {
+                session.end = Some(end.max(session.start));
+            }
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if self.is_active() || self.resumes_after_break() {
+            self.stop();
+        } else {
+            self.start();
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+pub enum TimerPopupInput {
+    Title,
+    Duration,
+    Tags,
+    Project,
 }
 
+impl TimerPopupInput {
+    /// The duration field is skipped when `has_duration` is false.
+    fn next(&self, has_duration: bool) -> Self {
+        match self {
+            TimerPopupInput::Title if has_duration => TimerPopupInput::Duration,
+            TimerPopupInput::Title | TimerPopupInput::Duration => TimerPopupInput::Tags,
+            TimerPopupInput::Tags => TimerPopupInput::Project,
+            TimerPopupInput::Project => TimerPopupInput::Title,
+        }
+    }
+
+    fn previous(&self, has_duration: bool) -> Self {
+        match self {
+            TimerPopupInput::Title => TimerPopupInput::Project,
+            TimerPopupInput::Duration => TimerPopupInput::Title,
+            TimerPopupInput::Tags if has_duration => TimerPopupInput::Duration,
+            TimerPopupInput::Tags => TimerPopupInput::Title,
+            TimerPopupInput::Project => TimerPopupInput::Tags,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum AdjustPopupInput {
+    Change,
+    ChangeDate,
+    Session,
+    SessionStart,
+    SessionEnd,
+}
+
+impl AdjustPopupInput {
+    fn next(&self) -> Self {
+        match self {
+            AdjustPopupInput::Change => AdjustPopupInput::ChangeDate,
+            AdjustPopupInput::ChangeDate => AdjustPopupInput::Session,
+            AdjustPopupInput::Session => AdjustPopupInput::SessionStart,
+            AdjustPopupInput::SessionStart => AdjustPopupInput::SessionEnd,
+            AdjustPopupInput::SessionEnd => AdjustPopupInput::Change,
+        }
+    }
+
+    fn previous(&self) -> Self {
+        match self {
+            AdjustPopupInput::Change => AdjustPopupInput::SessionEnd,
+            AdjustPopupInput::ChangeDate => AdjustPopupInput::Change,
+            AdjustPopupInput::Session => AdjustPopupInput::ChangeDate,
+            AdjustPopupInput::SessionStart => AdjustPopupInput::Session,
+            AdjustPopupInput::SessionEnd => AdjustPopupInput::SessionStart,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum IdleReason {
+    NoInput,
+    Suspended,
+}
+
+/// Stretch of time timers kept running without anyone around, waiting for a decision on
+/// what to do with it.
+pub struct IdlePrompt {
+    pub reason: IdleReason,
+    pub start: DateTime<Local>,
+    /// Unset while still idle, the next key press ends the period.
+    pub end: Option<DateTime<Local>>,
+    /// Index of the timer picked to receive the idle time while assigning.
+    pub assign_target: Option<usize>,
+}
+
+#[derive(PartialEq, Eq)]
+enum IdleResolution {
+    Keep,
+    Discard,
+    /// Moves the idle time from every timer to the timer at this index.
+    Assign(usize),
+}
+
+struct SessionEdit {
+    index: usize,
+    start: DateTime<Local>,
+    end: Option<DateTime<Local>>,
+}
+
+/// Time tracked for a task across its linked timers.
+#[derive(Clone, Copy, Default)]
+pub struct TrackedTime {
+    pub total: Duration,
+    pub is_running: bool,
+}
+
+const HISTORY_LIMIT: usize = 100;
+
 pub struct TimerState {
     pub timers: StatefulList<Timer>,
+    history: History<(Vec<Timer>, ListState)>,
+    pub pomodoro_config: PomodoroConfig,
+    pub timers_config: TimersConfig,
 
-    pub new_timer: Timer,
+    pub title_input: TextInput,
+    pub duration_input: TextInput,
+    pub tags_input: TextInput,
+    pub project_input: TextInput,
+    pub budget_input: TextInput,
+
+    pub filter_bar: FilterBar,
+    pub search_bar: SearchBar,
 
     pub new_timer_popup_enabled: bool,
+    pub creating_pomodoro: bool,
+    pub focused_input: TimerPopupInput,
+    pub popup_error: Option<String>,
+
+    pub change_input: TextInput,
+    pub change_date_input: TextInput,
+    pub session_input: TextInput,
+    pub session_start_input: TextInput,
+    pub session_end_input: TextInput,
+    pub adjust_popup_enabled: bool,
+    pub adjust_focused_input: AdjustPopupInput,
+    pub budget_popup_enabled: bool,
+    pub export_popup: ExportPopup,
 
-    pub last_tick: Instant,
+    pub idle_prompt: Option<IdlePrompt>,
+
+    notification: Option<String>,
+    pub is_dirty: bool,
 }
 
 impl TimerState {
-    pub fn new() -> Self {
+    pub fn new(
+        timers: Vec<Timer>,
+        pomodoro_config: PomodoroConfig,
+        timers_config: TimersConfig,
+    ) -> Self {
         Self {
-            timers: StatefulList::with_items(vec![]),
-            new_timer: Timer::default(),
+            timers: StatefulList::with_items(timers),
+            history: History::new(HISTORY_LIMIT),
+            pomodoro_config,
+            timers_config,
+            title_input: TextInput::default(),
+            duration_input: TextInput::default(),
+            tags_input: TextInput::default(),
+            project_input: TextInput::default(),
+            budget_input: TextInput::default(),
+            filter_bar: FilterBar::default(),
+            search_bar: SearchBar::default(),
             new_timer_popup_enabled: false,
-            last_tick: Instant::now(),
+            creating_pomodoro: false,
+            focused_input: TimerPopupInput::Title,
+            popup_error: None,
+            change_input: TextInput::default(),
+            change_date_input: TextInput::default(),
+            session_input: TextInput::default(),
+            session_start_input: TextInput::default(),
+            session_end_input: TextInput::default(),
+            adjust_popup_enabled: false,
+            adjust_focused_input: AdjustPopupInput::Change,
+            budget_popup_enabled: false,
+            export_popup: ExportPopup::default(),
+            idle_prompt: None,
+            notification: None,
+            is_dirty: false,
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter_bar.filter();
+        let search_query = self.search_bar.typed_query();
+
+        self.timers
+            .set_filter(if filter.is_empty() && search_query.is_none() {
+                None
+            } else {
+                Some(Box::new(move |timer: &Timer| {
+                    filter.matches(&timer.title, &timer.tags, timer.project.as_deref())
+                        && search_query
+                            .as_ref()
+                            .is_none_or(|query| fuzzy_match(query, &timer.title).is_some())
+                }))
+            });
+    }
+
+    /// Jumps to the next row matching the confirmed search query.
+    fn select_search_match(&mut self, forward: bool) {
+        if let Some(query) = &self.search_bar.query {
+            let rows = self.timers.visible_indices();
+            let row = find_match(rows.len(), self.timers.state.selected(), forward, |row| {
+                self.timers
+                    .items
+                    .get(rows[row])
+                    .is_some_and(|timer| fuzzy_match(query, &timer.title).is_some())
+            });
+            if row.is_some() {
+                self.timers.state.select(row);
+            }
+        }
+    }
+
+    fn record_history(&mut self) {
+        self.history
+            .record((self.timers.items.clone(), self.timers.state.clone()));
+    }
+
+    fn undo(&mut self) {
+        let current = (self.timers.items.clone(), self.timers.state.clone());
+        if let Some((items, state)) = self.history.undo(current) {
+            self.timers.items = items;
+            self.timers.state = state;
+            self.is_dirty = true;
+        }
+    }
+
+    fn redo(&mut self) {
+        let current = (self.timers.items.clone(), self.timers.state.clone());
+        if let Some((items, state)) = self.history.redo(current) {
+            self.timers.items = items;
+            self.timers.state = state;
+            self.is_dirty = true;
         }
     }
 
     fn delete_selected_timer(&mut self) {
-        self.timers.delete_current();
+        if self.timers.selected_index().is_some() {
+            self.record_history();
+            self.timers.delete_current();
+            self.is_dirty = true;
+        }
     }
 
-    fn open_create_popup(&mut self) {
+    fn toggle_selected_timer(&mut self) {
+        if self.timers.selected_index().is_none() {
+            return;
+        }
+
+        self.record_history();
+        if let Some(index) = self.timers.selected_index() {
+            let timer = &mut self.timers.items[index];
+            if timer.is_expired() {
+                timer.acknowledge_expiry();
+            } else {
+                timer.toggle();
+            }
+            if self.timers.items[index].is_active() {
+                self.pause_other_timers(index);
+            }
+            self.is_dirty = true;
+        }
+    }
+
+    /// In exclusive mode only the timer at `index` may keep running.
+    fn pause_other_timers(&mut self, index: usize) {
+        if !self.timers_config.exclusive {
+            return;
+        }
+
+        for (other_index, timer) in self.timers.items.iter_mut().enumerate() {
+            if other_index != index && (timer.is_active() || timer.resumes_after_break()) {
+                timer.stop();
+            }
+        }
+    }
+
+    /// Running timers, the most recently started first.
+    pub fn running_timers(&self) -> Vec<&Timer> {
+        let mut running_timers: Vec<&Timer> = self
+            .timers
+            .items
+            .iter()
+            .filter(|timer| timer.is_active())
+            .collect();
+        running_timers.sort_by_key(|timer| Reverse(timer.sessions.last().map(|s| s.start)));
+
+        running_timers
+    }
+
+    fn open_create_popup(&mut self, creating_pomodoro: bool) {
+        self.title_input.clear();
+        self.duration_input.clear();
+        self.tags_input.clear();
+        self.project_input.clear();
+        self.creating_pomodoro = creating_pomodoro;
         self.new_timer_popup_enabled = true;
     }
 
+    /// Whether a popup or an input bar is open.
+    pub fn is_editing(&self) -> bool {
+        self.new_timer_popup_enabled
+            || self.adjust_popup_enabled
+            || self.budget_popup_enabled
+            || self.export_popup.is_enabled
+            || self.filter_bar.is_enabled
+            || self.search_bar.is_enabled
+    }
+
+    /// Closes whichever popup is open without applying it, like Esc does.
+    pub fn close_popup(&mut self) {
+        if self.new_timer_popup_enabled {
+            self.close_create_popup();
+        }
+        if self.adjust_popup_enabled {
+            self.close_adjust_popup();
+        }
+        if self.budget_popup_enabled {
+            self.close_budget_popup();
+        }
+        self.export_popup.is_enabled = false;
+    }
+
     fn close_create_popup(&mut self) {
         self.new_timer_popup_enabled = false;
+        self.focused_input = TimerPopupInput::Title;
+        self.popup_error = None;
     }
 
-    fn create_new_timer(&mut self) {
-        let new_timer = Timer::new(self.new_timer.title.to_owned());
+    /// Creates the timer from the popup, keeping the popup open when the input is invalid.
+    fn create_new_timer(&mut self) -> Result<(), String> {
+        let kind = if self.creating_pomodoro {
+            TimerKind::Pomodoro(Pomodoro::new())
+        } else {
+            match parse_duration(self.duration_input.value())? {
+                Some(target) => TimerKind::Countdown(Countdown::new(target)),
+                None => TimerKind::Stopwatch,
+            }
+        };
+
+        let new_timer = Timer {
+            tags: parse_tags(self.tags_input.value()),
+            project: parse_project(self.project_input.value()),
+            kind,
+            ..Timer::new(self.title_input.value().to_owned())
+        };
+        self.record_history();
         self.timers.items.push(new_timer);
-        self.new_timer = Timer::default();
+        self.is_dirty = true;
+
+        Ok(())
     }
 
-    pub fn on_keycode(&mut self, key: KeyCode) -> bool {
-        if self.new_timer_popup_enabled {
-            self.on_popup_keycode(key);
+    fn open_adjust_popup(&mut self) {
+        let Some(index) = self.timers.selected_index() else {
+            return;
+        };
 
-            return true;
+        // Start out on the latest session, the one most likely left running by accident.
+        let session_count = self.timers.items[index].sessions.len();
+        self.change_input.clear();
+        self.change_date_input.clear();
+        self.session_input = if session_count > 0 {
+            TextInput::with_value(session_count.to_string())
+        } else {
+            TextInput::default()
+        };
+        self.load_session_inputs();
+        self.adjust_popup_enabled = true;
+    }
+
+    fn close_adjust_popup(&mut self) {
+        self.adjust_popup_enabled = false;
+        self.adjust_focused_input = AdjustPopupInput::Change;
+        self.popup_error = None;
+    }
+
+    /// Index of the session picked in the adjust popup, if the input names one.
+    fn picked_session(&self) -> Option<(usize, &TimerSession)> {
+        let timer = &self.timers.items[self.timers.selected_index()?];
+        let index = self.session_input.value().trim().parse::<usize>().ok()?;
+
+        index
+            .checked_sub(1)
+            .and_then(|index| timer.sessions.get(index).map(|session| (index, session)))
+    }
+
+    /// Fills start and end inputs with the picked session.
+    fn load_session_inputs(&mut self) {
+        let (start, end) = match self.picked_session() {
+            Some((_, session)) => (
+                session.start.format(SESSION_TIME_FORMAT).to_string(),
+                session
+                    .end
+                    .map(|end| end.format(SESSION_TIME_FORMAT).to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        self.session_start_input = TextInput::with_value(start);
+        self.session_end_input = TextInput::with_value(end);
+    }
+
+    /// Session edit requested in the adjust popup, if the start or end inputs changed.
+    fn parse_session_edit(&self) -> Result<Option<SessionEdit>, String> {
+        if self.session_input.value().trim().is_empty() {
+            return Ok(None);
         }
+        let (index, session) = self.picked_session().ok_or_else(|| {
+            format!(
+                "Session #{} does not exist",
+                self.session_input.value().trim()
+            )
+        })?;
 
-        match key {
-            KeyCode::Up => self.timers.previous(),
-            KeyCode::Down => self.timers.next(),
+        // Untouched inputs keep the exact original time instead of the minute shown.
+        let start_value = self.session_start_input.value().trim();
+        let end_value = self.session_end_input.value().trim();
+        let is_start_changed = start_value != session.start.format(SESSION_TIME_FORMAT).to_string();
+        let is_end_changed = end_value
+            != session
+                .end
+                .map(|end| end.format(SESSION_TIME_FORMAT).to_string())
+                .unwrap_or_default();
+        if !is_start_changed && !is_end_changed {
+            return Ok(None);
+        }
 
-            KeyCode::Enter => {
-                let current_selection = self.timers.state.selected();
-                if let Some(selection) = current_selection {
-                    self.timers.items[selection].is_active =
-                        !self.timers.items[selection].is_active;
-                }
+        let start_date = session.start.date_naive();
+        let start = if is_start_changed {
+            parse_session_time(start_value, start_date)?
+                .ok_or_else(|| "Session start is required".to_owned())?
+        } else {
+            session.start
+        };
+        let end = if is_end_changed {
+            let end_date = session.end.map_or(start_date, |end| end.date_naive());
+            parse_session_time(end_value, end_date)?
+        } else {
+            session.end
+        };
+
+        Ok(Some(SessionEdit { index, start, end }))
+    }
+
+    /// Applies the adjust popup to the selected timer, keeping the popup open when the input
+    /// is invalid.
+    fn confirm_adjustment(&mut self) -> Result<(), String> {
+        let Some(index) = self.timers.selected_index() else {
+            return Ok(());
+        };
+
+        // Changes go to a copy first so invalid input leaves neither timer nor history touched.
+        let mut timer = self.timers.items[index].clone();
+        if let Some(edit) = self.parse_session_edit()? {
+            timer.edit_session(edit.index, edit.start, edit.end)?;
+        }
+        let change = self.change_input.value().trim();
+        if !change.is_empty() {
+            // A correction entered after midnight usually belongs to the day before.
+            let today = Local::now().date_naive();
+            let date = match self.change_date_input.value().trim() {
+                "" => today,
+                value => parse_export_date(value, today)?,
+            };
+            if date > today {
+                return Err("Changes can't apply to a future day".to_owned());
             }
+            let day_total = timer.time_active_on(date);
+            if let Some(offset_seconds) = parse_adjustment(change, timer.time_active(), day_total)?
+            {
+                let day = if date == today {
+                    String::new()
+                } else {
+                    format!(" on {}", date)
+                };
+                timer.adjust_total(
+                    offset_seconds,
+                    date,
+                    format!(
+                        "Total changed with \"{}\" ({:+} min){}",
+                        change,
+                        offset_seconds / 60,
+                        day
+                    ),
+                );
+            }
+        }
 
-            KeyCode::Char(c) => match c {
-                'd' => {
-                    self.delete_selected_timer();
-                }
-                'n' => {
-                    self.open_create_popup();
-                    self.new_timer = Timer::default();
+        if timer.adjustments.len() != self.timers.items[index].adjustments.len() {
+            self.record_history();
+            self.timers.items[index] = timer;
+            self.is_dirty = true;
+        }
+
+        Ok(())
+    }
+
+    fn on_adjust_popup_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        let focused_input = match self.adjust_focused_input {
+            AdjustPopupInput::Change => &mut self.change_input,
+            AdjustPopupInput::ChangeDate => &mut self.change_date_input,
+            AdjustPopupInput::Session => &mut self.session_input,
+            AdjustPopupInput::SessionStart => &mut self.session_start_input,
+            AdjustPopupInput::SessionEnd => &mut self.session_end_input,
+        };
+        if focused_input.on_key(key, keymap) {
+            return;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(action @ (Action::NextField | Action::PreviousField)) => {
+                if self.adjust_focused_input == AdjustPopupInput::Session {
+                    self.load_session_inputs();
                 }
-                _ => {}
+                self.adjust_focused_input = if action == Action::NextField {
+                    self.adjust_focused_input.next()
+                } else {
+                    self.adjust_focused_input.previous()
+                };
+            }
+            Some(Action::Cancel) => {
+                self.close_adjust_popup();
+            }
+            Some(Action::Confirm) => match self.confirm_adjustment() {
+                Ok(()) => self.close_adjust_popup(),
+                Err(error) => self.popup_error = Some(error),
             },
             _ => {}
         };
+    }
+
+    fn open_budget_popup(&mut self) {
+        let Some(index) = self.timers.selected_index() else {
+            return;
+        };
+
+        self.budget_input = TextInput::with_value(
+            self.timers.items[index]
+                .budget
+                .map(Budget::to_input)
+                .unwrap_or_default(),
+        );
+        self.budget_popup_enabled = true;
+    }
+
+    fn close_budget_popup(&mut self) {
+        self.budget_popup_enabled = false;
+        self.popup_error = None;
+    }
+
+    /// Sets the budget of the selected timer, keeping the popup open when the input is invalid.
+    fn confirm_budget(&mut self) -> Result<(), String> {
+        let budget = parse_budget(self.budget_input.value())?;
+        if let Some(index) = self.timers.selected_index() {
+            self.record_history();
+            self.timers.items[index].budget = budget;
+            self.is_dirty = true;
+        }
+
+        Ok(())
+    }
+
+    fn on_budget_popup_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        if self.budget_input.on_key(key, keymap) {
+            return;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::Cancel) => {
+                self.close_budget_popup();
+            }
+            Some(Action::Confirm) => match self.confirm_budget() {
+                Ok(()) => self.close_budget_popup(),
+                Err(error) => self.popup_error = Some(error),
+            },
+            _ => {}
+        };
+    }
+
+    fn on_export_popup_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        match self.export_popup.on_key(key, keymap) {
+            ExportAction::None => {}
+            ExportAction::Close => self.export_popup.is_enabled = false,
+            ExportAction::Export(options) => {
+                let path = options.output.clone().unwrap_or_else(|| {
+                    dirs::home_dir()
+                        .unwrap_or_default()
+                        .join(options.default_file_name())
+                });
+
+                match export_to_file(&self.timers.items, &options, &path) {
+                    Ok(count) => {
+                        self.notification =
+                            Some(format!("Exported {} records to {}", count, path.display()));
+                        self.export_popup.is_enabled = false;
+                    }
+                    Err(error) => {
+                        self.export_popup.error =
+                            Some(format!("Could not write {}: {}", path.display(), error));
+                    }
+                };
+            }
+        };
+    }
+
+    /// Message for the header left by the last key press, if any.
+    pub fn take_notification(&mut self) -> Option<String> {
+        self.notification.take()
+    }
+
+    pub fn exceeded_budget_count(&self) -> usize {
+        self.timers
+            .items
+            .iter()
+            .filter(|timer| timer.is_over_budget())
+            .count()
+    }
+
+    /// Handles keys meant for open popups, input bars and search results, returning whether
+    /// the key was used. Everything else goes through the keymap.
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
+        if self.new_timer_popup_enabled {
+            self.on_popup_key(key, keymap);
+
+            return true;
+        }
+        if self.adjust_popup_enabled {
+            self.on_adjust_popup_key(key, keymap);
+
+            return true;
+        }
+        if self.budget_popup_enabled {
+            self.on_budget_popup_key(key, keymap);
+
+            return true;
+        }
+        if self.export_popup.is_enabled {
+            self.on_export_popup_key(key, keymap);
+
+            return true;
+        }
+        if self.filter_bar.is_enabled {
+            if self.filter_bar.on_key(key, keymap) {
+                self.apply_filter();
+            } else {
+                self.on_bar_key(key, keymap);
+            }
+
+            return true;
+        }
+        if self.search_bar.is_enabled {
+            if self.search_bar.on_key(key, keymap) {
+                self.apply_filter();
+            } else {
+                self.on_bar_key(key, keymap);
+            }
+
+            return true;
+        }
+        if self.search_bar.query.is_some() {
+            match keymap.action(KeyScope::Search, key) {
+                Some(Action::NextMatch) => self.select_search_match(true),
+                Some(Action::PreviousMatch) => self.select_search_match(false),
+                Some(Action::Cancel) => self.search_bar.clear(),
+                _ => return false,
+            };
+
+            return true;
+        }
 
         false
     }
 
-    fn on_popup_keycode(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Char(c) => {
-                self.new_timer.title.push(c);
+    /// Keys the filter or search bar left unused can still move through the narrowed list.
+    fn on_bar_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        match keymap.action(KeyScope::Timers, key) {
+            Some(Action::MoveUp) => self.timers.previous(),
+            Some(Action::MoveDown) => self.timers.next(),
+            _ => {}
+        };
+    }
+
+    /// Runs a keymap action, list movements repeat `count` times.
+    pub fn on_action(&mut self, action: Action, count: Option<usize>) {
+        match action {
+            Action::MoveUp => match count {
+                Some(count) => self.timers.move_by(-(count as isize)),
+                None => self.timers.previous(),
+            },
+            Action::MoveDown => match count {
+                Some(count) => self.timers.move_by(count as isize),
+                None => self.timers.next(),
+            },
+            // A count selects the row with that number, like "5G" in vim
+            Action::MoveTop | Action::MoveBottom if count.is_some() => {
+                self.timers.select_row(count.unwrap_or(1) - 1)
             }
-            KeyCode::Backspace => {
-                self.new_timer.title.pop();
+            Action::MoveTop => self.timers.select_row(0),
+            Action::MoveBottom => self.timers.select_row(usize::MAX),
+            Action::Toggle => self.toggle_selected_timer(),
+            Action::New => self.open_create_popup(false),
+            Action::NewPomodoro => self.open_create_popup(true),
+            Action::Adjust => self.open_adjust_popup(),
+            Action::Budget => self.open_budget_popup(),
+            Action::Delete => self.delete_selected_timer(),
+            Action::Export => self.export_popup.open(),
+            Action::Filter => self.filter_bar.open(),
+            Action::Search => self.search_bar.open(),
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            _ => {}
+        };
+    }
+
+    fn on_popup_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        let focused_input = match self.focused_input {
+            TimerPopupInput::Title => &mut self.title_input,
+            TimerPopupInput::Duration => &mut self.duration_input,
+            TimerPopupInput::Tags => &mut self.tags_input,
+            TimerPopupInput::Project => &mut self.project_input,
+        };
+        if focused_input.on_key(key, keymap) {
+            return;
+        }
+
+        match keymap.action(KeyScope::Popup, key) {
+            Some(Action::NextField) => {
+                self.focused_input = self.focused_input.next(!self.creating_pomodoro);
             }
-            KeyCode::Esc => {
-                self.close_create_popup();
+            Some(Action::PreviousField) => {
+                self.focused_input = self.focused_input.previous(!self.creating_pomodoro);
             }
-            KeyCode::Enter => {
-                self.create_new_timer();
+            Some(Action::Cancel) => {
                 self.close_create_popup();
             }
+            Some(Action::Confirm) => match self.create_new_timer() {
+                Ok(()) => self.close_create_popup(),
+                Err(error) => self.popup_error = Some(error),
+            },
             _ => {}
         };
     }
 
-    pub fn on_tick(&mut self) {
-        if self.last_tick.elapsed() > Duration::from_millis(1000) {
-            for timer in &mut self.timers.items.iter_mut() {
-                if timer.is_active {
-                    timer.time_active.add_assign(self.last_tick.elapsed());
+    /// Advances pomodoro phases and expires countdowns, returning an alert for every timer
+    /// whose phase ended or whose countdown ran out.
+    pub fn on_tick(&mut self) -> Vec<String> {
+        let mut alerts = vec![];
+        for index in 0..self.timers.items.len() {
+            let timer = &mut self.timers.items[index];
+            let was_active = timer.is_active();
+            let alert = timer
+                .advance_pomodoro(&self.pomodoro_config)
+                .or_else(|| timer.expire_countdown());
+            if let Some(alert) = alert {
+                alerts.push(alert);
+                self.is_dirty = true;
+            }
+            // A pomodoro starting again after its break
+            if !was_active && self.timers.items[index].is_active() {
+                self.pause_other_timers(index);
+            }
+        }
+
+        alerts
+    }
+
+    /// Resumes the timer linked to the task, creating one the first time time is tracked.
+    pub fn track_task(&mut self, task_id: u64, title: String) {
+        self.record_history();
+        let index = match self
+            .timers
+            .items
+            .iter()
+            .position(|timer| timer.task_id == Some(task_id))
+        {
+            Some(index) => index,
+            None => {
+                self.timers.items.push(Timer {
+                    task_id: Some(task_id),
+                    ..Timer::new(title)
+                });
+                self.timers.items.len() - 1
+            }
+        };
+
+        let timer = &mut self.timers.items[index];
+        if !timer.is_active() {
+            timer.start();
+        }
+        self.pause_other_timers(index);
+        self.is_dirty = true;
+    }
+
+    pub fn stop_task_timers(&mut self, task_id: u64) {
+        let is_linked_and_active =
+            |timer: &Timer| timer.task_id == Some(task_id) && timer.is_active();
+        if !self.timers.items.iter().any(is_linked_and_active) {
+            return;
+        }
+
+        self.record_history();
+        for timer in self.timers.items.iter_mut() {
+            if is_linked_and_active(timer) {
+                timer.stop();
+            }
+        }
+        self.is_dirty = true;
+    }
+
+    pub fn time_per_task(&self) -> HashMap<u64, TrackedTime> {
+        let mut time_per_task: HashMap<u64, TrackedTime> = HashMap::new();
+        for timer in &self.timers.items {
+            if let Some(task_id) = timer.task_id {
+                let tracked_time = time_per_task.entry(task_id).or_default();
+                tracked_time.total += timer.time_active();
+                tracked_time.is_running |= timer.is_active();
+            }
+        }
+
+        time_per_task
+    }
+
+    pub fn mark_idle(
+        &mut self,
+        reason: IdleReason,
+        start: DateTime<Local>,
+        end: Option<DateTime<Local>>,
+    ) {
+        self.idle_prompt = Some(IdlePrompt {
+            reason,
+            start,
+            end,
+            assign_target: None,
+        });
+    }
+
+    /// Keeps or discards the idle time, or picks a timer to move it to, with the keys of
+    /// the `[idle]` keymap scope.
+    pub fn on_idle_key(&mut self, key: KeyEvent, keymap: &Keymap) {
+        let timer_count = self.timers.items.len();
+        let selected_index = self.timers.selected_index();
+        let Some(idle_prompt) = &mut self.idle_prompt else {
+            return;
+        };
+        let end = *idle_prompt.end.get_or_insert_with(Local::now);
+        let start = idle_prompt.start;
+
+        let action = keymap.action(KeyScope::Idle, key);
+        if let Some(target) = idle_prompt.assign_target {
+            match action {
+                Some(Action::MoveUp) => {
+                    idle_prompt.assign_target = Some((target + timer_count - 1) % timer_count);
                 }
+                Some(Action::MoveDown) => {
+                    idle_prompt.assign_target = Some((target + 1) % timer_count);
+                }
+                Some(Action::Confirm) => {
+                    self.resolve_idle(start, end, IdleResolution::Assign(target))
+                }
+                Some(Action::Cancel) => idle_prompt.assign_target = None,
+                _ => {}
+            };
+
+            return;
+        }
+
+        match action {
+            Some(Action::Keep | Action::Cancel) => {
+                self.resolve_idle(start, end, IdleResolution::Keep)
             }
+            Some(Action::Discard) => self.resolve_idle(start, end, IdleResolution::Discard),
+            Some(Action::Assign) if timer_count > 0 => {
+                idle_prompt.assign_target = Some(selected_index.unwrap_or(0));
+            }
+            _ => {}
+        };
+    }
+
+    fn resolve_idle(
+        &mut self,
+        start: DateTime<Local>,
+        end: DateTime<Local>,
+        resolution: IdleResolution,
+    ) {
+        self.idle_prompt = None;
+        if resolution == IdleResolution::Keep || end <= start {
+            return;
+        }
 
-            self.last_tick = Instant::now();
+        self.record_history();
+        for timer in self.timers.items.iter_mut() {
+            timer.remove_interval(start, end);
         }
+        if let IdleResolution::Assign(target) = resolution {
+            if let Some(timer) = self.timers.items.get_mut(target) {
+                timer.insert_session(start, end);
+            }
+        }
+        self.is_dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{date, time_on};
+
+    fn time(hour: u32, minute: u32) -> DateTime<Local> {
+        time_on(date(2024, 5, 1), hour, minute)
+    }
+
+    fn session(start: DateTime<Local>, end: Option<DateTime<Local>>) -> TimerSession {
+        TimerSession { start, end }
+    }
+
+    fn session_times(timer: &Timer) -> Vec<(DateTime<Local>, Option<DateTime<Local>>)> {
+        timer
+            .sessions
+            .iter()
+            .map(|session| (session.start, session.end))
+            .collect()
+    }
+
+    #[test]
+    fn parses_durations() {
+        let minutes = |minutes: u64| Ok(Some(Duration::from_secs(minutes * 60)));
+
+        assert_eq!(parse_duration(""), Ok(None));
+        assert_eq!(parse_duration("25m"), minutes(25));
+        assert_eq!(parse_duration("1h30m"), minutes(90));
+        assert_eq!(parse_duration(" 1H 30m "), minutes(90));
+        assert_eq!(parse_duration("45"), minutes(45));
+        assert_eq!(parse_duration("90s"), Ok(Some(Duration::from_secs(90))));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        assert!(parse_duration("6000000000000000h").is_err());
+        assert!(parse_duration("400000000000000000").is_err());
+        assert!(parse_duration("18446744073709551615s 1s").is_err());
+    }
+
+    #[test]
+    fn parses_budgets() {
+        let budget = parse_budget("2h/day").unwrap().unwrap();
+        assert!(budget.period == BudgetPeriod::Daily);
+        assert_eq!(budget.limit, Duration::from_secs(2 * 3600));
+
+        let budget = parse_budget(" 5h30m / Week ").unwrap().unwrap();
+        assert!(budget.period == BudgetPeriod::Weekly);
+        assert_eq!(budget.limit, Duration::from_secs(5 * 3600 + 30 * 60));
+
+        assert!(parse_budget("").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_budgets() {
+        assert!(parse_budget("2h").is_err());
+        assert!(parse_budget("2h/month").is_err());
+        assert!(parse_budget("/day").is_err());
+        assert!(parse_budget("0m/day").is_err());
+    }
+
+    #[test]
+    fn parses_adjustments() {
+        let total = Duration::from_secs(3600);
+
+        assert_eq!(parse_adjustment("", total, total), Ok(None));
+        assert_eq!(parse_adjustment("30m", total, total), Ok(Some(1800)));
+        assert_eq!(parse_adjustment("+30m", total, total), Ok(Some(1800)));
+        assert_eq!(parse_adjustment("-15m", total, total), Ok(Some(-900)));
+        assert_eq!(parse_adjustment("=2h", total, total), Ok(Some(3600)));
+        assert_eq!(parse_adjustment("=15m", total, total), Ok(Some(-2700)));
+    }
+
+    #[test]
+    fn caps_subtractions_at_the_day_total() {
+        let total = Duration::from_secs(3600);
+        let day_total = Duration::from_secs(20 * 60);
+
+        assert_eq!(parse_adjustment("-1h", total, day_total), Ok(Some(-1200)));
+        assert_eq!(parse_adjustment("-1h", total, Duration::ZERO), Ok(Some(0)));
+        assert!(parse_adjustment("=15m", total, day_total).is_err());
+    }
+
+    #[test]
+    fn rejects_adjustments_that_overflow() {
+        let total = Duration::from_secs(3600);
+
+        assert!(parse_adjustment("+3000000000000000h", total, total).is_err());
+        assert!(parse_adjustment("-3000000000000000h", total, total).is_err());
+        assert!(parse_adjustment("=3000000000000000h", total, total).is_err());
+        assert!(parse_adjustment("+2562047788015215h", total, total).is_err());
+    }
+
+    #[test]
+    fn counts_adjustments_on_the_day_they_apply_to() {
+        let (monday, tuesday) = (date(2024, 5, 6), date(2024, 5, 7));
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![session(
+            time_on(monday, 22, 0),
+            Some(time_on(tuesday, 1, 0)),
+        )];
+        // Entered the next morning for the evening before
+        timer.adjustments = vec![TimeAdjustment {
+            made_at: time_on(tuesday, 9, 0),
+            applies_to: Some(monday),
+            offset_seconds: -3600,
+            description: String::new(),
+        }];
+
+        assert_eq!(timer.time_active_on(monday), Duration::from_secs(3600));
+        assert_eq!(timer.time_active_on(tuesday), Duration::from_secs(3600));
+        assert_eq!(timer.days()[1].offset_seconds, -3600);
+    }
+
+    #[test]
+    fn removes_interval_from_the_middle_of_a_session() {
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![session(time(10, 0), Some(time(12, 0)))];
+
+        timer.remove_interval(time(10, 30), time(11, 0));
+
+        assert_eq!(
+            session_times(&timer),
+            vec![
+                (time(10, 0), Some(time(10, 30))),
+                (time(11, 0), Some(time(12, 0)))
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_interval_from_a_running_session() {
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![session(time(10, 0), None)];
+
+        timer.remove_interval(time(10, 30), time(11, 0));
+
+        assert_eq!(
+            session_times(&timer),
+            vec![(time(10, 0), Some(time(10, 30))), (time(11, 0), None)]
+        );
+    }
+
+    #[test]
+    fn removes_sessions_inside_the_interval() {
+        let mut timer = Timer::new("Work".to_owned());
+        timer.sessions = vec![
+            session(time(9, 0), Some(time(9, 30))),
+            session(time(10, 0), Some(time(10, 30))),
+            session(time(10, 45), Some(time(11, 30))),
+            session(time(12, 0), Some(time(13, 0))),
+        ];
+
+        timer.remove_interval(time(9, 45), time(11, 0));
+
+        assert_eq!(
+            session_times(&timer),
+            vec![
+                (time(9, 0), Some(time(9, 30))),
+                (time(11, 0), Some(time(11, 30))),
+                (time(12, 0), Some(time(13, 0)))
+            ]
+        );
     }
 }
@@ -0,0 +1,1088 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::config::config_file_path;
+
+const KEYMAP_FILE_NAME: &str = "keymap.toml";
+
+/// Part of the app a binding applies to. Global bindings work in every tab unless the tab
+/// handles the key itself, popup bindings apply in every popup form and input bar.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyScope {
+    Global,
+    Tasks,
+    Timers,
+    Reports,
+    Popup,
+    /// Results of a confirmed search, ahead of the tab bindings.
+    Search,
+    /// Prompt about idle time, which takes every key until answered.
+    Idle,
+    /// Editing keys of text inputs, ahead of the popup bindings.
+    Input,
+}
+
+/// Action with its default keys and what it does, as listed in the help popup.
+type DefaultBinding = (Action, &'static [&'static str], &'static str);
+
+impl KeyScope {
+    pub const ALL: [KeyScope; 8] = [
+        KeyScope::Global,
+        KeyScope::Tasks,
+        KeyScope::Timers,
+        KeyScope::Reports,
+        KeyScope::Popup,
+        KeyScope::Search,
+        KeyScope::Idle,
+        KeyScope::Input,
+    ];
+
+    /// Section of the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyScope::Global => "global",
+            KeyScope::Tasks => "tasks",
+            KeyScope::Timers => "timers",
+            KeyScope::Reports => "reports",
+            KeyScope::Popup => "popup",
+            KeyScope::Search => "search",
+            KeyScope::Idle => "idle",
+            KeyScope::Input => "input",
+        }
+    }
+
+    /// Actions available in the scope with their default keys, in display order.
+    pub fn default_bindings(self) -> &'static [DefaultBinding] {
+        match self {
+            KeyScope::Global => &[
+                (Action::ShowTasks, &["1"], "Show tasks"),
+                (Action::ShowTimers, &["2"], "Show timers"),
+                (Action::ShowReports, &["3"], "Show reports"),
+                (Action::NextTab, &["right"], "Next tab"),
+                (Action::PreviousTab, &["left"], "Previous tab"),
+                (Action::Help, &["?"], "Show keys"),
+                (Action::Quit, &["q"], "Quit"),
+            ],
+            KeyScope::Tasks => &[
+                (Action::MoveUp, &["up"], "Select previous task"),
+                (Action::MoveDown, &["down"], "Select next task"),
+                (Action::MoveTop, &["home"], "Select first task"),
+                (Action::MoveBottom, &["end"], "Select last task"),
+                (Action::Toggle, &["enter"], "Complete or reopen task"),
+                (Action::New, &["n"], "New task"),
+                (Action::NewSubtask, &["a"], "New subtask"),
+                (Action::Edit, &["e"], "Edit task"),
+                (Action::Delete, &["d"], "Delete task"),
+                (Action::RaisePriority, &["+", "="], "Raise priority"),
+                (Action::LowerPriority, &["-"], "Lower priority"),
+                (
+                    Action::ToggleCollapse,
+                    &["space"],
+                    "Fold or unfold subtasks",
+                ),
+                (Action::SelectParent, &["p"], "Select parent task"),
+                (Action::CycleSort, &["s"], "Change sorting"),
+                (Action::TrackTime, &["t"], "Track time on task"),
+                (Action::Filter, &["f"], "Filter tasks"),
+                (Action::Search, &["/"], "Search tasks"),
+                (Action::Undo, &["u"], "Undo"),
+                (Action::Redo, &["ctrl+r"], "Redo"),
+            ],
+            KeyScope::Timers => &[
+                (Action::MoveUp, &["up"], "Select previous timer"),
+                (Action::MoveDown, &["down"], "Select next timer"),
+                (Action::MoveTop, &["home"], "Select first timer"),
+                (Action::MoveBottom, &["end"], "Select last timer"),
+                (Action::Toggle, &["enter"], "Start or stop timer"),
+                (Action::New, &["n"], "New timer"),
+                (Action::NewPomodoro, &["p"], "New pomodoro"),
+                (Action::Adjust, &["a"], "Adjust tracked time"),
+                (Action::Budget, &["b"], "Set budget"),
+                (Action::Delete, &["d"], "Delete timer"),
+                (Action::Export, &["x"], "Export timesheet"),
+                (Action::Filter, &["f"], "Filter timers"),
+                (Action::Search, &["/"], "Search timers"),
+                (Action::Undo, &["u"], "Undo"),
+                (Action::Redo, &["ctrl+r"], "Redo"),
+            ],
+            KeyScope::Reports => &[(Action::ToggleRange, &["r"], "Switch week and month")],
+            KeyScope::Popup => &[
+                (Action::Confirm, &["enter"], "Save"),
+                (Action::Cancel, &["esc"], "Close without saving"),
+                (Action::NextField, &["tab"], "Next field"),
+                (Action::PreviousField, &["backtab"], "Previous field"),
+                (Action::Help, &["f1"], "Show keys"),
+            ],
+            KeyScope::Search => &[
+                (Action::NextMatch, &["n"], "Next match"),
+                (Action::PreviousMatch, &["N"], "Previous match"),
+                (Action::Cancel, &["esc"], "Clear search"),
+            ],
+            KeyScope::Idle => &[
+                (Action::Keep, &["k"], "Keep idle time"),
+                (Action::Discard, &["d"], "Discard idle time"),
+                (Action::Assign, &["a"], "Assign idle time to another timer"),
+                (
+                    Action::MoveUp,
+                    &["up"],
+                    "Select previous timer to assign to",
+                ),
+                (
+                    Action::MoveDown,
+                    &["down"],
+                    "Select next timer to assign to",
+                ),
+                (Action::Confirm, &["enter"], "Assign to selected timer"),
+                (Action::Cancel, &["esc"], "Keep idle time or stop assigning"),
+            ],
+            KeyScope::Input => &[
+                (Action::CursorLeft, &["left"], "Move cursor left"),
+                (Action::CursorRight, &["right"], "Move cursor right"),
+                (
+                    Action::WordLeft,
+                    &["ctrl+left", "alt+left"],
+                    "Move to previous word",
+                ),
+                (
+                    Action::WordRight,
+                    &["ctrl+right", "alt+right"],
+                    "Move to next word",
+                ),
+                (Action::CursorStart, &["home"], "Move to start"),
+                (Action::CursorEnd, &["end"], "Move to end"),
+                (
+                    Action::DeleteBackward,
+                    &["backspace"],
+                    "Delete previous character",
+                ),
+                (Action::DeleteForward, &["delete"], "Delete next character"),
+                (
+                    Action::DeleteWordBackward,
+                    &["ctrl+w", "ctrl+backspace", "alt+backspace"],
+                    "Delete previous word",
+                ),
+                (
+                    Action::DeleteWordForward,
+                    &["ctrl+delete", "alt+delete"],
+                    "Delete next word",
+                ),
+                (Action::DeleteToStart, &["ctrl+u"], "Delete to start"),
+            ],
+        }
+    }
+}
+
+/// Named action a key can be bound to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+    Quit,
+    ShowTasks,
+    ShowTimers,
+    ShowReports,
+    NextTab,
+    PreviousTab,
+    Help,
+
+    MoveUp,
+    MoveDown,
+    MoveTop,
+    MoveBottom,
+    Toggle,
+    New,
+    NewSubtask,
+    NewPomodoro,
+    Edit,
+    Delete,
+    RaisePriority,
+    LowerPriority,
+    ToggleCollapse,
+    SelectParent,
+    CycleSort,
+    TrackTime,
+    Adjust,
+    Budget,
+    Export,
+    Filter,
+    Search,
+    Undo,
+    Redo,
+
+    ToggleRange,
+
+    Confirm,
+    Cancel,
+    NextField,
+    PreviousField,
+
+    NextMatch,
+    PreviousMatch,
+
+    Keep,
+    Discard,
+    Assign,
+
+    CursorLeft,
+    CursorRight,
+    WordLeft,
+    WordRight,
+    CursorStart,
+    CursorEnd,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWordBackward,
+    DeleteWordForward,
+    DeleteToStart,
+}
+
+impl Action {
+    /// Name used in the keymap file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ShowTasks => "show_tasks",
+            Action::ShowTimers => "show_timers",
+            Action::ShowReports => "show_reports",
+            Action::NextTab => "next_tab",
+            Action::PreviousTab => "previous_tab",
+            Action::Help => "help",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveTop => "move_top",
+            Action::MoveBottom => "move_bottom",
+            Action::Toggle => "toggle",
+            Action::New => "new",
+            Action::NewSubtask => "new_subtask",
+            Action::NewPomodoro => "new_pomodoro",
+            Action::Edit => "edit",
+            Action::Delete => "delete",
+            Action::RaisePriority => "raise_priority",
+            Action::LowerPriority => "lower_priority",
+            Action::ToggleCollapse => "toggle_collapse",
+            Action::SelectParent => "select_parent",
+            Action::CycleSort => "cycle_sort",
+            Action::TrackTime => "track_time",
+            Action::Adjust => "adjust",
+            Action::Budget => "budget",
+            Action::Export => "export",
+            Action::Filter => "filter",
+            Action::Search => "search",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ToggleRange => "toggle_range",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::NextField => "next_field",
+            Action::PreviousField => "previous_field",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::Keep => "keep",
+            Action::Discard => "discard",
+            Action::Assign => "assign",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorRight => "cursor_right",
+            Action::WordLeft => "word_left",
+            Action::WordRight => "word_right",
+            Action::CursorStart => "cursor_start",
+            Action::CursorEnd => "cursor_end",
+            Action::DeleteBackward => "delete_backward",
+            Action::DeleteForward => "delete_forward",
+            Action::DeleteWordBackward => "delete_word_backward",
+            Action::DeleteWordForward => "delete_word_forward",
+            Action::DeleteToStart => "delete_to_start",
+        }
+    }
+}
+
+/// Key together with the modifiers that have to be held.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parses bindings such as "q", "N", "ctrl+r", "alt+shift+left", "space" or "f5".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key \"{}\"", spec);
+        let (modifier_names, key_name) = match spec.strip_suffix("++") {
+            Some(modifier_names) => (modifier_names, "+"),
+            None => match spec.rsplit_once('+') {
+                Some((modifier_names, key_name)) if !key_name.is_empty() => {
+                    (modifier_names, key_name)
+                }
+                _ => ("", spec),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier_name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match modifier_name.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let mut chars = key_name.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key_name.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(number @ 1..=12) => KeyCode::F(number),
+                    _ => return Err(invalid()),
+                },
+            },
+        };
+
+        Ok(Self::normalized(code, modifiers))
+    }
+
+    pub fn from_event(key: KeyEvent) -> Self {
+        Self::normalized(key.code, key.modifiers)
+    }
+
+    fn digit(&self) -> Option<usize> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => {
+                c.to_digit(10).map(|digit| digit as usize)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(c) if c != ' ') && self.modifiers.is_empty()
+    }
+
+    /// Shift is part of the character itself ('N' rather than shift+'n'), so it is folded
+    /// into the key for characters and dropped where terminals report it inconsistently.
+    fn normalized(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_uppercase().next().unwrap_or(c)),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => Self {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    /// Characters a text input would insert.
+    fn is_typed(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.contains(KeyModifiers::CONTROL)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(number) => write!(f, "f{}", number),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::BackTab => f.write_str("backtab"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            _ => f.write_str("?"),
+        }
+    }
+}
+
+/// Keys pressed one after another to trigger an action, such as "g g".
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeySequence(Vec<KeyBinding>);
+
+impl KeySequence {
+    /// Parses space separated keys, e.g. "g g" or "ctrl+w j".
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let keys = match spec.trim() {
+            "" => vec![KeyBinding::parse(spec)?],
+            keys => keys
+                .split_whitespace()
+                .map(KeyBinding::parse)
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(Self(keys))
+    }
+
+    /// Counts typed in front of a sequence would swallow one starting with a digit.
+    fn starts_with_digit(&self) -> bool {
+        self.0.first().and_then(KeyBinding::digit).is_some()
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Plain characters read best run together, vim style ("gg", "dd").
+        let separator = if self.0.iter().all(KeyBinding::is_plain_char) {
+            ""
+        } else {
+            " "
+        };
+        for (index, key) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(separator)?;
+            }
+            write!(f, "{}", key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Set of bindings the keymap file starts from.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyProfile {
+    #[default]
+    Default,
+    /// Vim-style list navigation with count prefixes, e.g. "5j".
+    Vim,
+}
+
+impl KeyProfile {
+    /// Bindings replacing the defaults of the scope.
+    fn overrides(self, scope: KeyScope) -> &'static [(Action, &'static [&'static str])] {
+        match (self, scope) {
+            (KeyProfile::Default, _) => &[],
+            // Digits start counts, tabs switch with "gt" and "gT" like vim's tab pages.
+            (KeyProfile::Vim, KeyScope::Global) => &[
+                (Action::ShowTasks, &[]),
+                (Action::ShowTimers, &[]),
+                (Action::ShowReports, &[]),
+                (Action::NextTab, &["right", "g t"]),
+                (Action::PreviousTab, &["left", "g T"]),
+            ],
+            (KeyProfile::Vim, KeyScope::Tasks) => &[
+                (Action::MoveUp, &["up", "k"]),
+                (Action::MoveDown, &["down", "j"]),
+                (Action::MoveTop, &["home", "g g"]),
+                (Action::MoveBottom, &["end", "G"]),
+                (Action::Toggle, &["enter", "x"]),
+                (Action::Delete, &["d d"]),
+            ],
+            (KeyProfile::Vim, KeyScope::Timers) => &[
+                (Action::MoveUp, &["up", "k"]),
+                (Action::MoveDown, &["down", "j"]),
+                (Action::MoveTop, &["home", "g g"]),
+                (Action::MoveBottom, &["end", "G"]),
+                (Action::Toggle, &["enter", "x"]),
+                (Action::Delete, &["d d"]),
+                (Action::Export, &["X"]),
+            ],
+            (
+                KeyProfile::Vim,
+                KeyScope::Reports
+                | KeyScope::Popup
+                | KeyScope::Search
+                | KeyScope::Idle
+                | KeyScope::Input,
+            ) => &[],
+        }
+    }
+}
+
+/// One key or a list of keys for an action in the keymap file. An empty list unbinds it.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpecs {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Keymap file contents: the profile and a table per scope mapping action names to keys.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct KeymapFile {
+    profile: KeyProfile,
+    global: BTreeMap<String, KeySpecs>,
+    tasks: BTreeMap<String, KeySpecs>,
+    timers: BTreeMap<String, KeySpecs>,
+    reports: BTreeMap<String, KeySpecs>,
+    popup: BTreeMap<String, KeySpecs>,
+    search: BTreeMap<String, KeySpecs>,
+    idle: BTreeMap<String, KeySpecs>,
+    input: BTreeMap<String, KeySpecs>,
+}
+
+impl KeymapFile {
+    fn scope(&self, scope: KeyScope) -> &BTreeMap<String, KeySpecs> {
+        match scope {
+            KeyScope::Global => &self.global,
+            KeyScope::Tasks => &self.tasks,
+            KeyScope::Timers => &self.timers,
+            KeyScope::Reports => &self.reports,
+            KeyScope::Popup => &self.popup,
+            KeyScope::Search => &self.search,
+            KeyScope::Idle => &self.idle,
+            KeyScope::Input => &self.input,
+        }
+    }
+}
+
+/// Result of looking up the keys typed so far.
+enum KeyLookup {
+    Action(Action),
+    /// The keys start a longer sequence.
+    Pending,
+    Unbound,
+}
+
+/// Action with the key sequences bound to it.
+pub struct Binding {
+    pub action: Action,
+    pub sequences: Vec<KeySequence>,
+    pub description: &'static str,
+}
+
+/// Key sequences bound to every action, per scope.
+pub struct Keymap {
+    pub profile: KeyProfile,
+    bindings: HashMap<KeyScope, Vec<Binding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::build(&KeymapFile::default()).expect("default keymap is valid")
+    }
+}
+
+impl Keymap {
+    /// Profile bindings with the actions listed in the file rebound, failing on unknown
+    /// actions, invalid keys or keys bound twice.
+    fn build(file: &KeymapFile) -> Result<Self, String> {
+        let mut errors = vec![];
+        let mut bindings = HashMap::new();
+
+        for scope in KeyScope::ALL {
+            let defaults = scope.default_bindings();
+            let profile_overrides = file.profile.overrides(scope);
+            let overrides = file.scope(scope);
+            for name in overrides.keys() {
+                if !defaults.iter().any(|(action, _, _)| action.name() == name) {
+                    errors.push(format!("unknown action \"{}\" in [{}]", name, scope.name()));
+                }
+            }
+
+            let mut scope_bindings = vec![];
+            for (action, default_specs, description) in defaults {
+                let default_specs = profile_overrides
+                    .iter()
+                    .find(|(profile_action, _)| profile_action == action)
+                    .map_or(*default_specs, |(_, specs)| *specs);
+                let specs: Vec<&str> = match overrides.get(action.name()) {
+                    Some(KeySpecs::One(spec)) => vec![spec.as_str()],
+                    Some(KeySpecs::Many(specs)) => specs.iter().map(String::as_str).collect(),
+                    None => default_specs.to_vec(),
+                };
+
+                let mut sequences = vec![];
+                for spec in specs {
+                    match KeySequence::parse(spec) {
+                        Ok(sequence) => sequences.push(sequence),
+                        Err(error) => errors.push(format!("{} in [{}]", error, scope.name())),
+                    };
+                }
+                scope_bindings.push(Binding {
+                    action: *action,
+                    sequences,
+                    description,
+                });
+            }
+            bindings.insert(scope, scope_bindings);
+        }
+
+        let keymap = Self {
+            profile: file.profile,
+            bindings,
+        };
+        errors.extend(keymap.conflicts());
+        if errors.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    /// Sequences that make another one unreachable, either in the same scope, against the
+    /// global bindings or input keys against popup keys, sequences a count prefix would
+    /// swallow, keys that would be typed into an input and sequences where only single keys
+    /// are read.
+    fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = vec![];
+        let sequences = |scope| {
+            self.bindings(scope)
+                .iter()
+                .flat_map(move |binding| {
+                    binding
+                        .sequences
+                        .iter()
+                        .map(move |sequence| (sequence, binding.action, scope))
+                })
+                .collect::<Vec<_>>()
+        };
+        let global_sequences = sequences(KeyScope::Global);
+        let popup_sequences = sequences(KeyScope::Popup);
+
+        for scope in KeyScope::ALL {
+            let scope_sequences = sequences(scope);
+            for (index, first) in scope_sequences.iter().enumerate() {
+                conflicts.extend(
+                    scope_sequences[..index]
+                        .iter()
+                        .filter_map(|second| describe_conflict(*second, *first)),
+                );
+                if matches!(
+                    scope,
+                    KeyScope::Tasks | KeyScope::Timers | KeyScope::Reports
+                ) {
+                    conflicts.extend(
+                        global_sequences
+                            .iter()
+                            .filter_map(|global| describe_conflict(*first, *global)),
+                    );
+                }
+                if scope == KeyScope::Input {
+                    conflicts.extend(
+                        popup_sequences
+                            .iter()
+                            .filter_map(|popup| describe_conflict(*first, *popup)),
+                    );
+                }
+            }
+
+            // Popup and input keys are read one at a time, popup ones after the focused
+            // input had its turn.
+            if matches!(scope, KeyScope::Popup | KeyScope::Input) {
+                for (sequence, action, _) in &scope_sequences {
+                    if sequence.0.len() > 1 || sequence.0[0].is_typed() {
+                        conflicts.push(format!(
+                            "\"{}\" for \"{}\" in [{}] would be typed into the input, \
+                             use a single key with ctrl or a special key",
+                            sequence,
+                            action.name(),
+                            scope.name()
+                        ));
+                    }
+                }
+            }
+            // Search results and the idle prompt take keys ahead of the keymap, one at a time.
+            if matches!(scope, KeyScope::Search | KeyScope::Idle) {
+                for (sequence, action, _) in &scope_sequences {
+                    if sequence.0.len() > 1 {
+                        conflicts.push(format!(
+                            "\"{}\" for \"{}\" in [{}] has to be a single key",
+                            sequence,
+                            action.name(),
+                            scope.name()
+                        ));
+                    }
+                }
+            }
+
+            if self.accepts_count(scope) {
+                for (sequence, action, binding_scope) in
+                    scope_sequences.iter().chain(&global_sequences)
+                {
+                    if sequence.starts_with_digit() {
+                        conflicts.push(format!(
+                            "\"{}\" for \"{}\" in [{}] is read as a count in [{}]",
+                            sequence,
+                            action.name(),
+                            binding_scope.name(),
+                            scope.name()
+                        ));
+                    }
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    pub fn bindings(&self, scope: KeyScope) -> &[Binding] {
+        self.bindings.get(&scope).map_or(&[], Vec::as_slice)
+    }
+
+    /// Action bound to a single key in the scope, for popups where keys are not buffered.
+    pub fn action(&self, scope: KeyScope, key: KeyEvent) -> Option<Action> {
+        match self.lookup(scope, &[KeyBinding::from_event(key)]) {
+            KeyLookup::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// First key sequence bound to the action, for hints.
+    pub fn sequence(&self, scope: KeyScope, action: Action) -> Option<&KeySequence> {
+        self.bindings(scope)
+            .iter()
+            .find(|binding| binding.action == action)
+            .and_then(|binding| binding.sequences.first())
+    }
+
+    /// Counts are only typed in front of list actions of the vim profile.
+    fn accepts_count(&self, scope: KeyScope) -> bool {
+        self.profile == KeyProfile::Vim && matches!(scope, KeyScope::Tasks | KeyScope::Timers)
+    }
+
+    /// Looks the keys up in the scope, then among the global bindings for the tabs.
+    fn lookup(&self, scope: KeyScope, keys: &[KeyBinding]) -> KeyLookup {
+        let scopes = match scope {
+            KeyScope::Tasks | KeyScope::Timers | KeyScope::Reports => {
+                vec![scope, KeyScope::Global]
+            }
+            scope => vec![scope],
+        };
+
+        let mut is_pending = false;
+        for scope in scopes {
+            for binding in self.bindings(scope) {
+                for sequence in &binding.sequences {
+                    if sequence.0 == keys {
+                        return KeyLookup::Action(binding.action);
+                    }
+                    is_pending |= sequence.0.starts_with(keys);
+                }
+            }
+        }
+
+        if is_pending {
+            KeyLookup::Pending
+        } else {
+            KeyLookup::Unbound
+        }
+    }
+}
+
+type ScopedSequence<'a> = (&'a KeySequence, Action, KeyScope);
+
+fn describe_conflict(
+    (first, first_action, first_scope): ScopedSequence,
+    (second, second_action, second_scope): ScopedSequence,
+) -> Option<String> {
+    if first == second && first_scope == second_scope {
+        Some(format!(
+            "\"{}\" is bound to both \"{}\" and \"{}\" in [{}]",
+            first,
+            second_action.name(),
+            first_action.name(),
+            first_scope.name()
+        ))
+    } else if first == second {
+        Some(format!(
+            "\"{}\" is bound to \"{}\" in [{}] and \"{}\" in [{}]",
+            first,
+            first_action.name(),
+            first_scope.name(),
+            second_action.name(),
+            second_scope.name()
+        ))
+    } else {
+        let ((prefix, prefix_action, prefix_scope), (sequence, action, scope)) =
+            if second.0.starts_with(&first.0) {
+                (
+                    (first, first_action, first_scope),
+                    (second, second_action, second_scope),
+                )
+            } else if first.0.starts_with(&second.0) {
+                (
+                    (second, second_action, second_scope),
+                    (first, first_action, first_scope),
+                )
+            } else {
+                return None;
+            };
+        Some(format!(
+            "\"{}\" for \"{}\" in [{}] blocks \"{}\" for \"{}\" in [{}]",
+            prefix,
+            prefix_action.name(),
+            prefix_scope.name(),
+            sequence,
+            action.name(),
+            scope.name()
+        ))
+    }
+}
+
+/// How long a started sequence or count waits for its next key.
+const PENDING_KEYS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Largest count, small enough to be used as a signed offset.
+const MAX_COUNT: usize = isize::MAX as usize;
+
+/// Keys of a sequence typed so far, together with a count typed in front of them.
+#[derive(Default)]
+pub struct PendingKeys {
+    count: Option<usize>,
+    keys: Vec<KeyBinding>,
+    updated_at: Option<Instant>,
+}
+
+impl PendingKeys {
+    /// Adds a key, returning the action and its count once the keys complete a binding.
+    pub fn push(
+        &mut self,
+        keymap: &Keymap,
+        scope: KeyScope,
+        key: KeyEvent,
+    ) -> Option<(Action, Option<usize>)> {
+        let key = KeyBinding::from_event(key);
+        self.updated_at = Some(Instant::now());
+
+        if self.keys.is_empty() && keymap.accepts_count(scope) {
+            match (key.digit(), self.count) {
+                (Some(digit), Some(count)) => {
+                    self.count = Some(
+                        count
+                            .saturating_mul(10)
+                            .saturating_add(digit)
+                            .min(MAX_COUNT),
+                    );
+                    return None;
+                }
+                (Some(digit), None) if digit > 0 => {
+                    self.count = Some(digit);
+                    return None;
+                }
+                _ => {}
+            };
+        }
+
+        self.keys.push(key);
+        match keymap.lookup(scope, &self.keys) {
+            KeyLookup::Action(action) => {
+                let count = self.count;
+                self.clear();
+                Some((action, count))
+            }
+            KeyLookup::Pending => None,
+            KeyLookup::Unbound => {
+                self.clear();
+                None
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Drops keys that waited too long for the rest of their sequence.
+    pub fn expire(&mut self) {
+        if self
+            .updated_at
+            .is_some_and(|updated_at| updated_at.elapsed() >= PENDING_KEYS_TIMEOUT)
+        {
+            self.clear();
+        }
+    }
+}
+
+impl fmt::Display for PendingKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(count) = self.count {
+            write!(f, "{}", count)?;
+        }
+        for key in &self.keys {
+            write!(f, "{}", key)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Loads the keymap file, falling back to the default bindings when there is none.
+pub fn load() -> io::Result<Keymap> {
+    let path = config_file_path(KEYMAP_FILE_NAME)?;
+    if !path.exists() {
+        return Ok(Keymap::default());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    toml::from_str(&contents)
+        .map_err(|error| error.to_string())
+        .and_then(|file| Keymap::build(&file))
+        .map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), error),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyBinding {
+        KeyBinding { code, modifiers }
+    }
+
+    fn conflicts(contents: &str) -> String {
+        let file: KeymapFile = toml::from_str(contents).unwrap();
+        match Keymap::build(&file) {
+            Ok(_) => String::new(),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn parses_keys() {
+        let plain = KeyModifiers::NONE;
+
+        assert!(KeyBinding::parse("q") == Ok(key(KeyCode::Char('q'), plain)));
+        assert!(KeyBinding::parse("N") == Ok(key(KeyCode::Char('N'), plain)));
+        assert!(KeyBinding::parse("shift+n") == Ok(key(KeyCode::Char('N'), plain)));
+        assert!(KeyBinding::parse("+") == Ok(key(KeyCode::Char('+'), plain)));
+        assert!(KeyBinding::parse("space") == Ok(key(KeyCode::Char(' '), plain)));
+        assert!(KeyBinding::parse("Enter") == Ok(key(KeyCode::Enter, plain)));
+        assert!(KeyBinding::parse("f5") == Ok(key(KeyCode::F(5), plain)));
+        assert!(KeyBinding::parse("ctrl+r") == Ok(key(KeyCode::Char('r'), KeyModifiers::CONTROL)));
+        assert!(KeyBinding::parse("ctrl++") == Ok(key(KeyCode::Char('+'), KeyModifiers::CONTROL)));
+        assert!(
+            KeyBinding::parse("alt+shift+left")
+                == Ok(key(KeyCode::Left, KeyModifiers::ALT | KeyModifiers::SHIFT))
+        );
+        assert!(KeyBinding::parse("backtab") == KeyBinding::parse("shift+backtab"));
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(KeyBinding::parse("").is_err());
+        assert!(KeyBinding::parse("f13").is_err());
+        assert!(KeyBinding::parse("hyper+x").is_err());
+        assert!(KeyBinding::parse("escape key").is_err());
+    }
+
+    #[test]
+    fn displays_keys_the_way_they_are_parsed() {
+        for spec in ["q", "ctrl+r", "alt+shift+left", "space", "f5", "backtab"] {
+            assert_eq!(KeyBinding::parse(spec).unwrap().to_string(), spec);
+        }
+        assert_eq!(KeySequence::parse("g g").unwrap().to_string(), "gg");
+        assert_eq!(
+            KeySequence::parse("ctrl+w j").unwrap().to_string(),
+            "ctrl+w j"
+        );
+    }
+
+    #[test]
+    fn default_keymaps_have_no_conflicts() {
+        assert_eq!(conflicts(""), "");
+        assert_eq!(conflicts("profile = \"vim\""), "");
+    }
+
+    #[test]
+    fn reports_keys_bound_twice() {
+        assert!(conflicts("[tasks]\nnew = \"e\"")
+            .contains("\"e\" is bound to both \"edit\" and \"new\" in [tasks]"));
+        assert!(conflicts("[tasks]\nnew = \"q\"")
+            .contains("\"q\" is bound to \"new\" in [tasks] and \"quit\" in [global]"));
+    }
+
+    #[test]
+    fn reports_keys_typed_into_inputs() {
+        assert!(conflicts("[popup]\nconfirm = \"s\"")
+            .contains("\"s\" for \"confirm\" in [popup] would be typed into the input"));
+        assert!(conflicts("[input]\ncursor_start = \"a\"")
+            .contains("\"a\" for \"cursor_start\" in [input] would be typed into the input"));
+        assert!(conflicts("[input]\ncursor_start = \"enter\"").contains(
+            "\"enter\" is bound to \"cursor_start\" in [input] and \"confirm\" in [popup]"
+        ));
+    }
+
+    #[test]
+    fn reports_sequences_blocked_by_a_prefix() {
+        assert!(
+            conflicts("[tasks]\nmove_top = \"g\"\nmove_bottom = \"g g\"")
+                .contains("\"g\" for \"move_top\" in [tasks] blocks \"gg\" for \"move_bottom\"")
+        );
+    }
+
+    #[test]
+    fn reports_keys_read_as_counts() {
+        assert!(conflicts("profile = \"vim\"\n[tasks]\nnew = \"5\"")
+            .contains("\"5\" for \"new\" in [tasks] is read as a count in [tasks]"));
+        assert_eq!(conflicts("[tasks]\nnew = \"5\""), "");
+    }
+
+    #[test]
+    fn reports_sequences_where_single_keys_are_read() {
+        assert!(conflicts("[search]\nnext_match = \"g n\"")
+            .contains("\"gn\" for \"next_match\" in [search] has to be a single key"));
+    }
+
+    #[test]
+    fn reports_unknown_actions() {
+        assert!(conflicts("[timers]\nnew_subtask = \"a\"")
+            .contains("unknown action \"new_subtask\" in [timers]"));
+    }
+
+    #[test]
+    fn clamps_counts() {
+        let keymap = Keymap::build(&toml::from_str("profile = \"vim\"").unwrap())
+            .unwrap_or_else(|error| panic!("{}", error));
+        let mut pending_keys = PendingKeys::default();
+        let digit = KeyEvent::new(KeyCode::Char('9'), KeyModifiers::NONE);
+        for _ in 0..30 {
+            assert!(pending_keys.push(&keymap, KeyScope::Tasks, digit).is_none());
+        }
+
+        let action = pending_keys.push(
+            &keymap,
+            KeyScope::Tasks,
+            KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE),
+        );
+        assert!(action == Some((Action::MoveDown, Some(MAX_COUNT))));
+    }
+}
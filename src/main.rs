@@ -1,13 +1,37 @@
 mod app;
+mod cli;
+mod config;
 mod crossterm;
 mod features;
+mod keymap;
+mod storage;
 mod structures;
+#[cfg(test)]
+mod test_support;
+mod theme;
 mod ui;
 
-use crate::crossterm::run;
-use std::{error::Error, time::Duration};
+use crate::{cli::Command, crossterm::run};
+use std::{error::Error, process, time::Duration};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    run(Duration::from_millis(250), true)?;
-    Ok(())
+fn main() {
+    let command = cli::parse(std::env::args().skip(1)).unwrap_or_else(|error| {
+        eprintln!("{}\n\n{}", error, cli::USAGE);
+        process::exit(2);
+    });
+
+    let result: Result<(), Box<dyn Error>> = match command {
+        Command::Tui => run(Duration::from_millis(250), true),
+        Command::Export(options) => cli::export(&options),
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+    };
+
+    // Config and keymap errors name the offending file, print them as they are
+    if let Err(error) = result {
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
 }
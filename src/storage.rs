@@ -0,0 +1,206 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::features::{
+    tasks::Task,
+    timers::{Timer, TimerSession},
+};
+
+const STORAGE_VERSION: u32 = 2;
+
+const APP_DIRECTORY: &str = "productivity-tui";
+const STATE_FILE_NAME: &str = "state.json";
+
+#[derive(Default, Deserialize)]
+pub struct StoredState {
+    pub tasks: Vec<Task>,
+    pub timers: Vec<Timer>,
+    /// Id the next new task gets, so ids of deleted tasks are never handed out again.
+    /// Missing from files written before it was stored.
+    #[serde(default)]
+    pub next_task_id: u64,
+}
+
+#[derive(Serialize)]
+struct StoredStateRef<'a> {
+    version: u32,
+    tasks: &'a [Task],
+    timers: &'a [Timer],
+    next_task_id: u64,
+}
+
+#[derive(Deserialize)]
+struct StoredVersion {
+    version: u32,
+}
+
+/// Version 1 stored accumulated durations instead of sessions.
+#[derive(Deserialize)]
+struct StoredStateV1 {
+    tasks: Vec<Task>,
+    timers: Vec<TimerV1>,
+}
+
+#[derive(Deserialize)]
+struct TimerV1 {
+    title: String,
+    is_active: bool,
+    time_active: std::time::Duration,
+    time_created: DateTime<Local>,
+}
+
+impl From<TimerV1> for Timer {
+    // The original intervals are unknown, so the logged total becomes one session from creation.
+    fn from(timer: TimerV1) -> Self {
+        let mut sessions = vec![];
+        if !timer.time_active.is_zero() {
+            let time_active = chrono::Duration::from_std(timer.time_active).unwrap_or_default();
+            sessions.push(TimerSession {
+                start: timer.time_created,
+                end: Some(timer.time_created + time_active),
+            });
+        }
+        if timer.is_active {
+            sessions.push(TimerSession::starting_now());
+        }
+
+        Timer {
+            sessions,
+            time_created: timer.time_created,
+            ..Timer::new(timer.title)
+        }
+    }
+}
+
+pub fn state_file_path() -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join(APP_DIRECTORY).join(STATE_FILE_NAME))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "could not determine the data directory",
+            )
+        })
+}
+
+/// Loads the persisted state, falling back to an empty state on first launch.
+pub fn load() -> io::Result<StoredState> {
+    let path = state_file_path()?;
+    if !path.exists() {
+        return Ok(StoredState::default());
+    }
+
+    let contents = fs::read_to_string(&path)?;
+    from_json(&contents, &path)
+}
+
+/// Reads the state file contents, migrating older formats. `path` is only used for errors.
+fn from_json(contents: &str, path: &Path) -> io::Result<StoredState> {
+    let StoredVersion { version } = serde_json::from_str(contents)?;
+    if version > STORAGE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} was written by a newer version (format {}, supported up to {})",
+                path.display(),
+                version,
+                STORAGE_VERSION
+            ),
+        ));
+    }
+
+    if version == 1 {
+        let state: StoredStateV1 = serde_json::from_str(contents)?;
+        return Ok(StoredState {
+            tasks: state.tasks,
+            timers: state.timers.into_iter().map(Timer::from).collect(),
+            next_task_id: 0,
+        });
+    }
+
+    Ok(serde_json::from_str(contents)?)
+}
+
+pub fn save(tasks: &[Task], timers: &[Timer], next_task_id: u64) -> io::Result<()> {
+    let path = state_file_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let state = StoredStateRef {
+        version: STORAGE_VERSION,
+        tasks,
+        timers,
+        next_task_id,
+    };
+    let contents = serde_json::to_string_pretty(&state)?;
+
+    write_atomically(&path, &contents)
+}
+
+// Write next to the target first so a crash mid-write never leaves a truncated file behind.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let temporary_path = path.with_extension("json.tmp");
+    fs::write(&temporary_path, contents)?;
+    fs::rename(&temporary_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v1_timers_to_sessions() {
+        let state = from_json(
+            r#"{
+                "version": 1,
+                "tasks": [],
+                "timers": [
+                    {
+                        "title": "Logged",
+                        "is_active": false,
+                        "time_active": { "secs": 5400, "nanos": 0 },
+                        "time_created": "2024-05-01T10:00:00+00:00"
+                    },
+                    {
+                        "title": "Running",
+                        "is_active": true,
+                        "time_active": { "secs": 0, "nanos": 0 },
+                        "time_created": "2024-05-01T10:00:00+00:00"
+                    }
+                ]
+            }"#,
+            Path::new("state.json"),
+        )
+        .unwrap();
+
+        let [logged, running] = &state.timers[..] else {
+            panic!("expected two timers");
+        };
+        assert_eq!(logged.title, "Logged");
+        assert_eq!(logged.sessions.len(), 1);
+        assert_eq!(logged.sessions[0].start, logged.time_created);
+        assert_eq!(
+            logged.sessions[0].end,
+            Some(logged.time_created + chrono::Duration::minutes(90))
+        );
+        assert_eq!(running.sessions.len(), 1);
+        assert!(running.sessions[0].end.is_none());
+        assert_eq!(state.next_task_id, 0);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let state = from_json(
+            r#"{ "version": 3, "tasks": [], "timers": [] }"#,
+            Path::new("state.json"),
+        );
+
+        assert!(state.is_err());
+    }
+}
@@ -0,0 +1,82 @@
+/// Bounded undo and redo stacks of state snapshots.
+pub struct History<S> {
+    undo_stack: Vec<S>,
+    redo_stack: Vec<S>,
+    limit: usize,
+}
+
+impl<S> History<S> {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo_stack: vec![],
+            redo_stack: vec![],
+            limit,
+        }
+    }
+
+    /// Stores the state from before a change. Any undone changes can't be redone anymore.
+    pub fn record(&mut self, snapshot: S) {
+        self.undo_stack.push(snapshot);
+        if self.undo_stack.len() > self.limit {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Returns the state to restore, `current` becomes available to redo.
+    pub fn undo(&mut self, current: S) -> Option<S> {
+        let snapshot = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        Some(snapshot)
+    }
+
+    /// Returns the state to restore, `current` becomes available to undo.
+    pub fn redo(&mut self, current: S) -> Option<S> {
+        let snapshot = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        Some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undoes_and_redoes_in_order() {
+        let mut history = History::new(10);
+        history.record(1);
+        history.record(2);
+
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), None);
+    }
+
+    #[test]
+    fn drops_the_oldest_state_over_the_limit() {
+        let mut history = History::new(2);
+        for state in 1..=3 {
+            history.record(state);
+        }
+
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+    }
+
+    #[test]
+    fn clears_redo_on_a_new_change() {
+        let mut history = History::new(10);
+        history.record(1);
+        assert_eq!(history.undo(2), Some(1));
+
+        history.record(1);
+
+        assert_eq!(history.redo(3), None);
+        assert_eq!(history.undo(3), Some(1));
+    }
+}
@@ -1 +1,4 @@
+pub mod history;
 pub mod stateful_list;
+pub mod stateful_tree;
+pub mod text_input;
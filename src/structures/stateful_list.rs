@@ -1,8 +1,16 @@
+use std::ops::Range;
+
 use tui::widgets::ListState;
 
+pub type ItemPredicate<T> = Box<dyn Fn(&T) -> bool>;
+
+/// List with a selection. An optional filter hides items without removing them, the list
+/// state then selects among the visible items only.
 pub struct StatefulList<T> {
     pub state: ListState,
     pub items: Vec<T>,
+    filter: Option<ItemPredicate<T>>,
+    offset: usize,
 }
 
 impl<T> StatefulList<T> {
@@ -10,13 +18,48 @@ impl<T> StatefulList<T> {
         StatefulList {
             state: ListState::default(),
             items,
+            filter: None,
+            offset: 0,
         }
     }
 
+    /// Indices into `items` of every visible item in display order.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| self.filter.as_ref().is_none_or(|filter| filter(item)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    pub fn set_filter(&mut self, filter: Option<ItemPredicate<T>>) {
+        let selected_index = self.selected_index();
+        self.filter = filter;
+
+        // Follow the selected item, falling back to the first match when it got filtered out.
+        let row = selected_index
+            .and_then(|index| self.visible_indices().iter().position(|i| *i == index));
+        match (row, self.state.selected()) {
+            (Some(row), _) => self.state.select(Some(row)),
+            (None, Some(_)) => self.state.select(Some(0)),
+            (None, None) if self.filter.is_some() => self.state.select(Some(0)),
+            (None, None) => {}
+        };
+    }
+
+    /// Index into `items` of the selected item.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.state
+            .selected()
+            .and_then(|selection| self.visible_indices().get(selection).copied())
+    }
+
     pub fn next(&mut self) {
+        let visible_count = self.visible_indices().len();
         let i = match self.state.selected() {
             Some(idx) => {
-                if idx + 1 >= self.items.len() {
+                if idx + 1 >= visible_count {
                     0
                 } else {
                     idx + 1
@@ -28,12 +71,13 @@ impl<T> StatefulList<T> {
     }
 
     pub fn previous(&mut self) {
+        let visible_count = self.visible_indices().len();
         let i = match self.state.selected() {
             Some(i) => {
-                if self.items.is_empty() {
+                if visible_count == 0 {
                     0
-                } else if i == 0 && !self.items.is_empty() {
-                    self.items.len() - 1
+                } else if i == 0 {
+                    visible_count - 1
                 } else {
                     i - 1
                 }
@@ -43,16 +87,119 @@ impl<T> StatefulList<T> {
         self.state.select(Some(i));
     }
 
+    /// Moves the selection by `steps` rows, stopping at the first and last row.
+    pub fn move_by(&mut self, steps: isize) {
+        let row = self.state.selected().unwrap_or(0);
+        self.select_row(row.saturating_add_signed(steps));
+    }
+
+    /// Selects the row at `row`, or the last one when there are fewer rows.
+    pub fn select_row(&mut self, row: usize) {
+        let row_count = self.visible_indices().len();
+        if row_count > 0 {
+            self.state.select(Some(row.min(row_count - 1)));
+        }
+    }
+
+    /// Visible rows that fit into `max_height` given the height of every visible row,
+    /// scrolled just enough to keep the selection in view. Drawing the window instead of
+    /// the whole list makes row positions known, e.g. for widgets drawn on top of rows.
+    pub fn scroll_window(&mut self, heights: &[usize], max_height: usize) -> Range<usize> {
+        scroll_window(&mut self.offset, self.state.selected(), heights, max_height)
+    }
+
     pub fn delete_current(&mut self) {
-        if let Some(idx) = self.state.selected() {
-            if idx < self.items.len() {
-                self.items.remove(idx);
-                if idx == 0 {
-                    self.state.select(Some(0));
-                } else {
-                    self.previous();
-                }
+        if let (Some(idx), Some(index)) = (self.state.selected(), self.selected_index()) {
+            self.items.remove(index);
+            if idx == 0 {
+                self.state.select(Some(0));
+            } else {
+                self.previous();
             }
         };
     }
 }
+
+/// Window of rows starting at `offset` or as close to it as the `selected` row allows,
+/// updating `offset` to the new start.
+pub fn scroll_window(
+    offset: &mut usize,
+    selected: Option<usize>,
+    heights: &[usize],
+    max_height: usize,
+) -> Range<usize> {
+    if heights.is_empty() {
+        *offset = 0;
+        return 0..0;
+    }
+
+    let mut start = (*offset).min(heights.len() - 1);
+    let mut end = start;
+    let mut height = 0;
+    for row_height in &heights[start..] {
+        if height + row_height > max_height {
+            break;
+        }
+        height += row_height;
+        end += 1;
+    }
+
+    let selected = selected.unwrap_or(0).min(heights.len() - 1);
+    while selected >= end {
+        height += heights[end];
+        end += 1;
+        while height > max_height {
+            height -= heights[start];
+            start += 1;
+        }
+    }
+    while selected < start {
+        start -= 1;
+        height += heights[start];
+        while height > max_height {
+            end -= 1;
+            height -= heights[end];
+        }
+    }
+
+    *offset = start;
+    start..end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_every_row_that_fits() {
+        let mut offset = 0;
+
+        assert_eq!(scroll_window(&mut offset, Some(2), &[1, 2, 1], 5), 0..3);
+        assert_eq!(scroll_window(&mut offset, None, &[], 5), 0..0);
+        assert_eq!(offset, 0);
+    }
+
+    #[test]
+    fn scrolls_just_enough_to_show_the_selection() {
+        let heights = [2; 5];
+        let mut offset = 0;
+
+        assert_eq!(scroll_window(&mut offset, Some(3), &heights, 4), 2..4);
+        assert_eq!(offset, 2);
+        assert_eq!(scroll_window(&mut offset, Some(2), &heights, 4), 2..4);
+        assert_eq!(scroll_window(&mut offset, Some(0), &heights, 4), 0..2);
+        assert_eq!(offset, 0);
+    }
+
+    #[test]
+    fn deletes_the_selected_item_under_a_filter() {
+        let mut list = StatefulList::with_items(vec![1, 2, 3, 4, 5]);
+        list.set_filter(Some(Box::new(|item: &i32| item % 2 == 0)));
+        list.state.select(Some(1));
+
+        list.delete_current();
+
+        assert_eq!(list.items, vec![1, 2, 3, 5]);
+        assert_eq!(list.selected_index(), Some(1));
+    }
+}
@@ -0,0 +1,414 @@
+use std::{cmp::Ordering, ops::Range};
+
+use tui::widgets::ListState;
+
+use super::stateful_list::{scroll_window, ItemPredicate};
+
+/// Nodes stored in a `StatefulTree`.
+pub trait TreeItem: Sized {
+    fn children(&self) -> &[Self];
+    fn children_mut(&mut self) -> &mut Vec<Self>;
+    fn is_collapsed(&self) -> bool;
+    fn set_collapsed(&mut self, collapsed: bool);
+}
+
+/// Tree counterpart of `StatefulList`. Items are addressed by their path of child indices,
+/// the list state selects among the currently visible rows.
+pub struct StatefulTree<T> {
+    pub state: ListState,
+    pub items: Vec<T>,
+    filter: Option<ItemPredicate<T>>,
+    offset: usize,
+}
+
+impl<T: TreeItem> StatefulTree<T> {
+    pub fn with_items(items: Vec<T>) -> StatefulTree<T> {
+        StatefulTree {
+            state: ListState::default(),
+            items,
+            filter: None,
+            offset: 0,
+        }
+    }
+
+    /// Paths of every visible row in display order. Children of collapsed items are skipped
+    /// unless a filter is set, which shows every match together with its ancestors.
+    pub fn rows(&self) -> Vec<Vec<usize>> {
+        let mut rows = vec![];
+        match &self.filter {
+            Some(filter) => {
+                collect_filtered_rows(&self.items, filter, &mut vec![], &mut rows);
+            }
+            None => collect_rows(&self.items, &mut vec![], &mut rows),
+        };
+        rows
+    }
+
+    pub fn set_filter(&mut self, filter: Option<ItemPredicate<T>>) {
+        let selected_path = self.selected_path();
+        self.filter = filter;
+
+        // Follow the selected item, falling back to the first match when it got filtered out.
+        let row = selected_path.and_then(|path| self.rows().iter().position(|row| *row == path));
+        match (row, self.state.selected()) {
+            (Some(row), _) => self.state.select(Some(row)),
+            (None, Some(_)) => self.state.select(Some(0)),
+            (None, None) if self.filter.is_some() => self.state.select(Some(0)),
+            (None, None) => {}
+        };
+    }
+
+    pub fn get(&self, path: &[usize]) -> Option<&T> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.items.get(*first)?, |item, index| {
+                item.children().get(*index)
+            })
+    }
+
+    pub fn get_mut(&mut self, path: &[usize]) -> Option<&mut T> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.items.get_mut(*first)?, |item, index| {
+                item.children_mut().get_mut(*index)
+            })
+    }
+
+    pub fn selected_path(&self) -> Option<Vec<usize>> {
+        self.state
+            .selected()
+            .and_then(|selection| self.rows().into_iter().nth(selection))
+    }
+
+    pub fn selected_mut(&mut self) -> Option<&mut T> {
+        let path = self.selected_path()?;
+        self.get_mut(&path)
+    }
+
+    pub fn select_path(&mut self, path: &[usize]) {
+        if let Some(row) = self.rows().iter().position(|row| row == path) {
+            self.state.select(Some(row));
+        }
+    }
+
+    pub fn next(&mut self) {
+        let row_count = self.rows().len();
+        let i = match self.state.selected() {
+            Some(idx) => {
+                if idx + 1 >= row_count {
+                    0
+                } else {
+                    idx + 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        let row_count = self.rows().len();
+        let i = match self.state.selected() {
+            Some(i) => {
+                if row_count == 0 {
+                    0
+                } else if i == 0 {
+                    row_count - 1
+                } else {
+                    i - 1
+                }
+            }
+            None => 0,
+        };
+        self.state.select(Some(i));
+    }
+
+    /// Moves the selection by `steps` rows, stopping at the first and last row.
+    pub fn move_by(&mut self, steps: isize) {
+        let row = self.state.selected().unwrap_or(0);
+        self.select_row(row.saturating_add_signed(steps));
+    }
+
+    /// Selects the row at `row`, or the last one when there are fewer rows.
+    pub fn select_row(&mut self, row: usize) {
+        let row_count = self.rows().len();
+        if row_count > 0 {
+            self.state.select(Some(row.min(row_count - 1)));
+        }
+    }
+
+    /// Moves the selection one nesting level up.
+    pub fn select_parent(&mut self) {
+        if let Some(mut path) = self.selected_path() {
+            if path.len() > 1 {
+                path.pop();
+                self.select_path(&path);
+            }
+        }
+    }
+
+    /// Visible rows that fit into `max_height`, see `StatefulList::scroll_window`.
+    pub fn scroll_window(&mut self, heights: &[usize], max_height: usize) -> Range<usize> {
+        scroll_window(&mut self.offset, self.state.selected(), heights, max_height)
+    }
+
+    pub fn toggle_collapsed(&mut self) {
+        if let Some(path) = self.selected_path() {
+            if let Some(item) = self.get_mut(&path) {
+                if !item.children().is_empty() {
+                    let collapsed = item.is_collapsed();
+                    item.set_collapsed(!collapsed);
+                }
+            }
+            self.select_path(&path);
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+
+    /// Appends `item` as the last child of `parent`, expanding the parent so it stays visible.
+    pub fn push_child(&mut self, parent: &[usize], item: T) {
+        if let Some(parent_item) = self.get_mut(parent) {
+            parent_item.set_collapsed(false);
+            parent_item.children_mut().push(item);
+
+            let mut child_path = parent.to_vec();
+            child_path.push(parent_item.children().len() - 1);
+            self.select_path(&child_path);
+        }
+    }
+
+    /// Sorts every level of the tree while keeping the selection on the same item.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let selected_path = self.selected_path();
+        let selected_path = sort_level(&mut self.items, selected_path.as_deref(), &mut compare);
+
+        if let Some(path) = selected_path {
+            self.select_path(&path);
+        }
+    }
+
+    /// Removes the selected item together with all of its children.
+    pub fn delete_current(&mut self) {
+        if let (Some(idx), Some(path)) = (self.state.selected(), self.selected_path()) {
+            let (last, parent) = path.split_last().expect("paths are never empty");
+            if parent.is_empty() {
+                self.items.remove(*last);
+            } else if let Some(parent_item) = self.get_mut(parent) {
+                parent_item.children_mut().remove(*last);
+            }
+
+            let row_count = self.rows().len();
+            self.state
+                .select(Some(idx.min(row_count.saturating_sub(1))));
+        };
+    }
+}
+
+fn collect_rows<T: TreeItem>(items: &[T], prefix: &mut Vec<usize>, rows: &mut Vec<Vec<usize>>) {
+    for (index, item) in items.iter().enumerate() {
+        prefix.push(index);
+        rows.push(prefix.clone());
+        if !item.is_collapsed() {
+            collect_rows(item.children(), prefix, rows);
+        }
+        prefix.pop();
+    }
+}
+
+// Returns whether any item of `items` or their descendants matched.
+fn collect_filtered_rows<T: TreeItem>(
+    items: &[T],
+    filter: &ItemPredicate<T>,
+    prefix: &mut Vec<usize>,
+    rows: &mut Vec<Vec<usize>>,
+) -> bool {
+    let mut any_matched = false;
+    for (index, item) in items.iter().enumerate() {
+        prefix.push(index);
+
+        let row = rows.len();
+        rows.push(prefix.clone());
+        let children_matched = collect_filtered_rows(item.children(), filter, prefix, rows);
+        if children_matched || filter(item) {
+            any_matched = true;
+        } else {
+            rows.remove(row);
+        }
+
+        prefix.pop();
+    }
+    any_matched
+}
+
+// Returns where the item addressed by `selected_path` ended up after sorting.
+fn sort_level<T, F>(
+    items: &mut Vec<T>,
+    selected_path: Option<&[usize]>,
+    compare: &mut F,
+) -> Option<Vec<usize>>
+where
+    T: TreeItem,
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut indexed_items: Vec<(usize, T)> = items.drain(..).enumerate().collect();
+    indexed_items.sort_by(|(_, a), (_, b)| compare(a, b));
+
+    let mut new_selected_path = None;
+    for (new_index, (old_index, mut item)) in indexed_items.into_iter().enumerate() {
+        let child_selected_path = match selected_path {
+            Some([first, rest @ ..]) if *first == old_index => Some(rest),
+            _ => None,
+        };
+
+        let sorted_child_path = sort_level(item.children_mut(), child_selected_path, compare);
+        if child_selected_path.is_some() {
+            let mut path = vec![new_index];
+            path.extend(sorted_child_path.unwrap_or_default());
+            new_selected_path = Some(path);
+        }
+
+        items.push(item);
+    }
+
+    new_selected_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Node {
+        name: &'static str,
+        children: Vec<Node>,
+        is_collapsed: bool,
+    }
+
+    impl TreeItem for Node {
+        fn children(&self) -> &[Self] {
+            &self.children
+        }
+
+        fn children_mut(&mut self) -> &mut Vec<Self> {
+            &mut self.children
+        }
+
+        fn is_collapsed(&self) -> bool {
+            self.is_collapsed
+        }
+
+        fn set_collapsed(&mut self, collapsed: bool) {
+            self.is_collapsed = collapsed;
+        }
+    }
+
+    fn node(name: &'static str, children: Vec<Node>) -> Node {
+        Node {
+            name,
+            children,
+            is_collapsed: false,
+        }
+    }
+
+    /// a (b (c), d), e
+    fn tree() -> StatefulTree<Node> {
+        StatefulTree::with_items(vec![
+            node(
+                "a",
+                vec![node("b", vec![node("c", vec![])]), node("d", vec![])],
+            ),
+            node("e", vec![]),
+        ])
+    }
+
+    fn selected_name(tree: &StatefulTree<Node>) -> Option<&'static str> {
+        tree.selected_path()
+            .and_then(|path| tree.get(&path))
+            .map(|node| node.name)
+    }
+
+    #[test]
+    fn lists_rows_depth_first() {
+        let rows = tree().rows();
+
+        assert_eq!(
+            rows,
+            vec![vec![0], vec![0, 0], vec![0, 0, 0], vec![0, 1], vec![1]]
+        );
+    }
+
+    #[test]
+    fn skips_children_of_collapsed_items() {
+        let mut tree = tree();
+        tree.select_path(&[0, 0]);
+
+        tree.toggle_collapsed();
+
+        assert_eq!(tree.rows(), vec![vec![0], vec![0, 0], vec![0, 1], vec![1]]);
+        assert_eq!(selected_name(&tree), Some("b"));
+    }
+
+    #[test]
+    fn expands_the_parent_of_a_new_child() {
+        let mut tree = tree();
+        tree.items[0].is_collapsed = true;
+
+        tree.push_child(&[0], node("f", vec![]));
+
+        assert_eq!(tree.rows().len(), 6);
+        assert_eq!(selected_name(&tree), Some("f"));
+    }
+
+    #[test]
+    fn selects_the_parent() {
+        let mut tree = tree();
+        tree.select_path(&[0, 0, 0]);
+
+        tree.select_parent();
+        assert_eq!(selected_name(&tree), Some("b"));
+        tree.select_parent();
+        tree.select_parent();
+        assert_eq!(selected_name(&tree), Some("a"));
+    }
+
+    #[test]
+    fn deletes_an_item_with_its_children() {
+        let mut tree = tree();
+        tree.select_path(&[0, 0]);
+
+        tree.delete_current();
+
+        assert_eq!(tree.rows(), vec![vec![0], vec![0, 0], vec![1]]);
+        assert_eq!(selected_name(&tree), Some("d"));
+    }
+
+    #[test]
+    fn keeps_the_selection_when_sorting() {
+        let mut tree = tree();
+        tree.select_path(&[0, 0, 0]);
+
+        tree.sort_by(|a, b| b.name.cmp(a.name));
+
+        assert_eq!(tree.items[0].name, "e");
+        assert_eq!(tree.items[1].children[0].name, "d");
+        assert_eq!(tree.selected_path(), Some(vec![1, 1, 0]));
+        assert_eq!(selected_name(&tree), Some("c"));
+    }
+
+    #[test]
+    fn keeps_ancestors_of_filtered_items() {
+        let mut tree = tree();
+        tree.items[0].is_collapsed = true;
+        tree.select_path(&[1]);
+
+        tree.set_filter(Some(Box::new(|node: &Node| node.name == "c")));
+
+        assert_eq!(tree.rows(), vec![vec![0], vec![0, 0], vec![0, 0, 0]]);
+        assert_eq!(selected_name(&tree), Some("a"));
+    }
+}
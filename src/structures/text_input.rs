@@ -0,0 +1,203 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::keymap::{Action, KeyScope, Keymap};
+
+/// Single line text input. The cursor and scroll offset are counted in grapheme clusters
+/// so combined characters and emoji are edited as one unit.
+#[derive(Default)]
+pub struct TextInput {
+    value: String,
+    cursor: usize,
+    scroll: usize,
+}
+
+impl TextInput {
+    pub fn with_value(value: String) -> Self {
+        let cursor = value.graphemes(true).count();
+        Self {
+            value,
+            cursor,
+            scroll: 0,
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns whether the key was consumed by the input. Editing keys come from the
+    /// `[input]` keymap scope, any other character without ctrl is typed.
+    pub fn on_key(&mut self, key: KeyEvent, keymap: &Keymap) -> bool {
+        match keymap.action(KeyScope::Input, key) {
+            Some(Action::CursorLeft) => self.cursor = self.cursor.saturating_sub(1),
+            Some(Action::CursorRight) => self.cursor = (self.cursor + 1).min(self.len()),
+            Some(Action::WordLeft) => self.cursor = self.previous_word_boundary(),
+            Some(Action::WordRight) => self.cursor = self.next_word_boundary(),
+            Some(Action::CursorStart) => self.cursor = 0,
+            Some(Action::CursorEnd) => self.cursor = self.len(),
+            Some(Action::DeleteBackward) => self.delete_backward(),
+            Some(Action::DeleteForward) => self.delete_forward(),
+            Some(Action::DeleteWordBackward) => self.delete_word_backward(),
+            Some(Action::DeleteWordForward) => self.delete_word_forward(),
+            Some(Action::DeleteToStart) => self.delete_to_start(),
+            _ => match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.insert(c)
+                }
+                _ => return false,
+            },
+        };
+
+        true
+    }
+
+    /// Adjusts the scroll offset so the cursor fits into `width` columns and returns the
+    /// visible slice of the value together with the cursor column inside it.
+    pub fn visible(&mut self, width: usize) -> (String, usize) {
+        let graphemes: Vec<&str> = self.value.graphemes(true).collect();
+        // Keep one column free for the cursor at the end of the value.
+        let width = width.saturating_sub(1).max(1);
+
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        }
+        while graphemes[self.scroll..self.cursor].concat().width() > width {
+            self.scroll += 1;
+        }
+
+        let mut visible = String::new();
+        for grapheme in &graphemes[self.scroll..] {
+            if visible.width() + grapheme.width() > width {
+                break;
+            }
+            visible.push_str(grapheme);
+        }
+        let cursor_column = graphemes[self.scroll..self.cursor].concat().width();
+
+        (visible, cursor_column)
+    }
+
+    fn len(&self) -> usize {
+        self.value.graphemes(true).count()
+    }
+
+    fn byte_offset(&self, grapheme_index: usize) -> usize {
+        self.value
+            .grapheme_indices(true)
+            .nth(grapheme_index)
+            .map_or(self.value.len(), |(offset, _)| offset)
+    }
+
+    fn insert(&mut self, c: char) {
+        let offset = self.byte_offset(self.cursor);
+        self.value.insert(offset, c);
+        // The character can merge with the clusters around it, e.g. a combining accent or a
+        // zero width joiner between two emoji, so the cursor goes behind its cluster.
+        let inserted_end = offset + c.len_utf8();
+        self.cursor = self
+            .value
+            .grapheme_indices(true)
+            .take_while(|(start, _)| *start < inserted_end)
+            .count();
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        let start_offset = self.byte_offset(start);
+        let end_offset = self.byte_offset(end);
+        self.value.replace_range(start_offset..end_offset, "");
+        self.cursor = start;
+    }
+
+    fn delete_backward(&mut self) {
+        if self.cursor > 0 {
+            self.delete_range(self.cursor - 1, self.cursor);
+        }
+    }
+
+    fn delete_forward(&mut self) {
+        if self.cursor < self.len() {
+            self.delete_range(self.cursor, self.cursor + 1);
+        }
+    }
+
+    fn delete_word_backward(&mut self) {
+        let boundary = self.previous_word_boundary();
+        self.delete_range(boundary, self.cursor);
+    }
+
+    fn delete_word_forward(&mut self) {
+        let boundary = self.next_word_boundary();
+        self.delete_range(self.cursor, boundary);
+    }
+
+    fn delete_to_start(&mut self) {
+        self.delete_range(0, self.cursor);
+    }
+
+    fn previous_word_boundary(&self) -> usize {
+        let graphemes: Vec<&str> = self.value.graphemes(true).collect();
+        let mut index = self.cursor;
+        while index > 0 && is_whitespace(graphemes[index - 1]) {
+            index -= 1;
+        }
+        while index > 0 && !is_whitespace(graphemes[index - 1]) {
+            index -= 1;
+        }
+        index
+    }
+
+    fn next_word_boundary(&self) -> usize {
+        let graphemes: Vec<&str> = self.value.graphemes(true).collect();
+        let mut index = self.cursor;
+        while index < graphemes.len() && is_whitespace(graphemes[index]) {
+            index += 1;
+        }
+        while index < graphemes.len() && !is_whitespace(graphemes[index]) {
+            index += 1;
+        }
+        index
+    }
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(value: &str, cursor: usize, c: char) -> TextInput {
+        let mut input = TextInput::with_value(value.to_owned());
+        input.cursor = cursor;
+        input.insert(c);
+        input
+    }
+
+    #[test]
+    fn moves_the_cursor_behind_inserted_characters() {
+        let input = typed("ac", 1, 'b');
+        assert_eq!((input.value(), input.cursor), ("abc", 2));
+
+        let input = typed("e", 1, '\u{301}');
+        assert_eq!((input.value(), input.cursor), ("e\u{301}", 1));
+    }
+
+    #[test]
+    fn keeps_the_cursor_when_clusters_join() {
+        let input = typed("👨👩", 1, '\u{200D}');
+        assert_eq!((input.value(), input.cursor), ("👨\u{200D}👩", 1));
+
+        let input = typed("\u{1100}\u{11A8}", 1, '\u{1161}');
+        assert_eq!(
+            (input.value(), input.cursor),
+            ("\u{1100}\u{1161}\u{11A8}", 1)
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! Builders shared by the unit tests.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+/// Local time on `date`, which has to exist in the local time zone.
+pub fn time_on(date: NaiveDate, hour: u32, minute: u32) -> DateTime<Local> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(hour, minute, 0).unwrap())
+        .unwrap()
+}
@@ -0,0 +1,328 @@
+use std::{env, io};
+
+use serde::Deserialize;
+use tui::style::{Color, Modifier, Style};
+
+use crate::config::{config_file_path, Config, CONFIG_FILE_NAME};
+
+/// Colors the UI draws with, named after what they stand for.
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// Secondary text and unfocused borders.
+    pub muted: Color,
+    /// Notifications, focused inputs and hints.
+    pub accent: Color,
+    /// Background of the selected list row.
+    pub selection: Color,
+    /// Running timers, completed tasks and the selected tab.
+    pub active: Color,
+    /// Stopped timers and open tasks.
+    pub inactive: Color,
+    /// Overdue tasks and exceeded budgets.
+    pub warning: Color,
+    pub error: Color,
+    /// Upcoming due dates and countdown progress.
+    pub info: Color,
+    /// Tags, projects and budgets.
+    pub label: Color,
+    /// Time tracked on tasks.
+    pub tracked: Color,
+    /// Expired countdowns.
+    pub expired: Color,
+    /// Text drawn on top of colored bars and gauges.
+    pub background: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        muted: Color::Gray,
+        accent: Color::Yellow,
+        selection: Color::Rgb(50, 50, 50),
+        active: Color::Green,
+        inactive: Color::Red,
+        warning: Color::LightRed,
+        error: Color::Red,
+        info: Color::Cyan,
+        label: Color::Blue,
+        tracked: Color::Magenta,
+        expired: Color::LightMagenta,
+        background: Color::Black,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        muted: Color::Rgb(110, 110, 110),
+        accent: Color::Rgb(175, 95, 0),
+        selection: Color::Rgb(215, 215, 215),
+        active: Color::Rgb(0, 135, 0),
+        inactive: Color::Rgb(175, 0, 0),
+        warning: Color::Rgb(215, 0, 0),
+        error: Color::Rgb(215, 0, 0),
+        info: Color::Rgb(0, 110, 150),
+        label: Color::Rgb(0, 60, 190),
+        tracked: Color::Rgb(135, 0, 135),
+        expired: Color::Rgb(175, 0, 175),
+        background: Color::White,
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        muted: Color::White,
+        accent: Color::LightYellow,
+        selection: Color::Blue,
+        active: Color::LightGreen,
+        inactive: Color::LightRed,
+        warning: Color::LightYellow,
+        error: Color::LightRed,
+        info: Color::LightCyan,
+        label: Color::LightCyan,
+        tracked: Color::LightMagenta,
+        expired: Color::LightMagenta,
+        background: Color::Black,
+    };
+
+    /// Dark theme limited to the 16 colors every terminal has.
+    pub const SIXTEEN_COLOR: Theme = Theme {
+        selection: Color::DarkGray,
+        ..Theme::DARK
+    };
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::DARK),
+            "light" => Some(Theme::LIGHT),
+            "high-contrast" => Some(Theme::HIGH_CONTRAST),
+            "16-color" => Some(Theme::SIXTEEN_COLOR),
+            _ => None,
+        }
+    }
+
+    /// Theme selected in the config, either built in or one of the user-defined themes.
+    fn from_config(config: &Config) -> Result<Theme, String> {
+        let name = config.theme.name.as_str();
+        if let Some(theme) = Theme::built_in(name) {
+            return Ok(theme);
+        }
+
+        let custom_theme = config
+            .themes
+            .get(name)
+            .ok_or_else(|| format!("unknown theme \"{}\"", name))?;
+        let base = Theme::built_in(&custom_theme.base).ok_or_else(|| {
+            format!(
+                "unknown base theme \"{}\" in [themes.{}]",
+                custom_theme.base, name
+            )
+        })?;
+
+        Ok(custom_theme.apply(base))
+    }
+
+    /// Same theme with every color replaced by the closest of the 16 basic colors.
+    pub fn basic(self) -> Theme {
+        Theme {
+            muted: basic_color(self.muted),
+            accent: basic_color(self.accent),
+            // The closest match would often be the terminal background itself.
+            selection: match basic_color(self.selection) {
+                Color::Black => Color::DarkGray,
+                Color::White => Color::Gray,
+                color => color,
+            },
+            active: basic_color(self.active),
+            inactive: basic_color(self.inactive),
+            warning: basic_color(self.warning),
+            error: basic_color(self.error),
+            info: basic_color(self.info),
+            label: basic_color(self.label),
+            tracked: basic_color(self.tracked),
+            expired: basic_color(self.expired),
+            background: basic_color(self.background),
+        }
+    }
+
+    /// Theme leaving every color to the terminal, for `NO_COLOR`.
+    pub fn no_color() -> Theme {
+        Theme {
+            muted: Color::Reset,
+            accent: Color::Reset,
+            selection: Color::Reset,
+            active: Color::Reset,
+            inactive: Color::Reset,
+            warning: Color::Reset,
+            error: Color::Reset,
+            info: Color::Reset,
+            label: Color::Reset,
+            tracked: Color::Reset,
+            expired: Color::Reset,
+            background: Color::Reset,
+        }
+    }
+
+    /// Style of the selected list row, reversed when there is no selection color.
+    pub fn selection_style(&self) -> Style {
+        match self.selection {
+            Color::Reset => Style::default().add_modifier(Modifier::REVERSED),
+            selection => Style::default().bg(selection),
+        }
+    }
+}
+
+/// Color in the config file: a name such as "light-red", "#rrggbb" or a 256-color index.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let name: String = value
+            .to_lowercase()
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect();
+        let color = match name.as_str() {
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            name => match name.strip_prefix('#') {
+                Some(hex) if hex.len() == 6 => u32::from_str_radix(hex, 16)
+                    .map(|rgb| Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+                    .map_err(|_| format!("invalid color \"{}\"", value))?,
+                _ => name
+                    .parse()
+                    .map(Color::Indexed)
+                    .map_err(|_| format!("invalid color \"{}\"", value))?,
+            },
+        };
+
+        Ok(ThemeColor(color))
+    }
+}
+
+/// User-defined theme, colors it leaves out come from its `base` theme.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTheme {
+    #[serde(default = "default_base")]
+    base: String,
+    muted: Option<ThemeColor>,
+    accent: Option<ThemeColor>,
+    selection: Option<ThemeColor>,
+    active: Option<ThemeColor>,
+    inactive: Option<ThemeColor>,
+    warning: Option<ThemeColor>,
+    error: Option<ThemeColor>,
+    info: Option<ThemeColor>,
+    label: Option<ThemeColor>,
+    tracked: Option<ThemeColor>,
+    expired: Option<ThemeColor>,
+    background: Option<ThemeColor>,
+}
+
+fn default_base() -> String {
+    String::from("dark")
+}
+
+impl CustomTheme {
+    fn apply(&self, base: Theme) -> Theme {
+        let color = |color: Option<ThemeColor>, base_color| color.map_or(base_color, |c| c.0);
+        Theme {
+            muted: color(self.muted, base.muted),
+            accent: color(self.accent, base.accent),
+            selection: color(self.selection, base.selection),
+            active: color(self.active, base.active),
+            inactive: color(self.inactive, base.inactive),
+            warning: color(self.warning, base.warning),
+            error: color(self.error, base.error),
+            info: color(self.info, base.info),
+            label: color(self.label, base.label),
+            tracked: color(self.tracked, base.tracked),
+            expired: color(self.expired, base.expired),
+            background: color(self.background, base.background),
+        }
+    }
+}
+
+/// The 16 basic colors with their usual xterm values.
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn basic_color(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index @ 0..=15) => return BASIC_COLORS[index as usize].0,
+        // 6x6x6 color cube
+        Color::Indexed(index @ 16..=231) => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        // Grayscale ramp
+        Color::Indexed(index) => {
+            let value = 8 + (index - 232) * 10;
+            (value, value, value)
+        }
+        color => return color,
+    };
+
+    let distance = |(red, green, blue): (u8, u8, u8)| {
+        [(r, red), (g, green), (b, blue)]
+            .iter()
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum::<i32>()
+    };
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map_or(color, |(basic, _)| *basic)
+}
+
+/// Theme from the config, reduced to the basic palette without enhanced graphics and
+/// without any color when `NO_COLOR` is set.
+pub fn load(config: &Config, enhanced_graphics: bool) -> io::Result<Theme> {
+    let theme = Theme::from_config(config).map_err(|error| {
+        let path = config_file_path(CONFIG_FILE_NAME).unwrap_or_default();
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), error),
+        )
+    })?;
+
+    if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        Ok(Theme::no_color())
+    } else if enhanced_graphics {
+        Ok(theme)
+    } else {
+        Ok(theme.basic())
+    }
+}
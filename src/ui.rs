@@ -1,26 +1,60 @@
-use crate::app::App;
+use std::{ops::Range, time::Duration};
+
+use crate::{
+    app::{App, MouseAreas},
+    features::{
+        export::ExportPopupInput,
+        filter::FilterBar,
+        reports::{completed_per_day, tracked_per_day, tracked_per_timer},
+        search::{fuzzy_match, SearchBar},
+        tasks::{DueStatus, TaskPopupInput, TaskPopupTarget, TaskSortMode},
+        timers::{
+            format_duration, start_of_day, start_of_week, AdjustPopupInput, Countdown, IdleReason,
+            TimerKind, TimerPopupInput,
+        },
+    },
+    keymap::{Action, KeyProfile, KeyScope, Keymap},
+    structures::text_input::TextInput,
+    theme::Theme,
+};
+use chrono::Local;
 use tui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Tabs},
+    widgets::{
+        BarChart, Block, BorderType, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph,
+        Sparkline, Tabs, Widget, Wrap,
+    },
     Frame,
 };
 use unicode_width::UnicodeWidthStr;
 
-// TODO: Code up input field component which would handle offscreen and other issues?
-
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let theme = app.theme;
+    // The vim profile keeps a status line for keys of unfinished sequences
+    let status_line_height = match app.keymap.profile {
+        KeyProfile::Vim => 1,
+        KeyProfile::Default => 0,
+    };
     let chunks = Layout::default()
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(status_line_height),
+            ]
+            .as_ref(),
+        )
         .split(f.size());
 
     let tab_titles = app
         .tabs
         .titles
         .iter()
-        .map(|t| Spans::from(Span::styled(*t, Style::default().fg(Color::Gray))))
+        .map(|t| Spans::from(Span::styled(*t, Style::default().fg(theme.muted))))
         .collect();
 
     let tabs = Tabs::new(tab_titles)
@@ -30,80 +64,782 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
                 .borders(Borders::ALL)
                 .title(app.title),
         )
-        .highlight_style(Style::default().fg(Color::Green))
+        .highlight_style(Style::default().fg(theme.active))
         .select(app.tabs.index);
 
     f.render_widget(tabs, chunks[0]);
+    app.mouse_areas = MouseAreas {
+        tabs: tab_title_areas(&app.tabs.titles, chunks[0]),
+        ..MouseAreas::default()
+    };
+    draw_header_status(f, app, chunks[0]);
+    if status_line_height > 0 {
+        draw_status_line(f, app, chunks[2]);
+    }
 
     match app.tabs.index {
         0 => draw_task_tab(f, app, chunks[1]),
         1 => draw_timers_tab(f, app, chunks[1]),
+        2 => draw_reports_tab(f, app, chunks[1]),
         _ => {}
     };
 
     if app.task_state.new_task_popup_enabled {
-        draw_new_task_popup(f, app, chunks[1]);
+        app.mouse_areas.popup = Some(draw_new_task_popup(f, app, chunks[1]));
     }
     if app.timer_state.new_timer_popup_enabled {
-        draw_new_timer_popup(f, app, chunks[1]);
+        app.mouse_areas.popup = Some(draw_new_timer_popup(f, app, chunks[1]));
+    }
+    if app.timer_state.adjust_popup_enabled {
+        app.mouse_areas.popup = Some(draw_adjust_timer_popup(f, app, chunks[1]));
+    }
+    if app.timer_state.budget_popup_enabled {
+        let timer_state = &mut app.timer_state;
+        let fields = vec![FormField {
+            label: "Budget (e.g. 2h/day or 5h/week)",
+            is_focused: true,
+            input: &mut timer_state.budget_input,
+        }];
+        app.mouse_areas.popup = Some(draw_form_popup(
+            f,
+            &theme,
+            "Timer Budget",
+            fields,
+            timer_state.popup_error.as_deref(),
+            chunks[1],
+        ));
+    }
+    if app.timer_state.export_popup.is_enabled {
+        app.mouse_areas.popup = Some(draw_export_popup(f, app, chunks[1]));
+    }
+    if app.help_enabled {
+        app.mouse_areas.popup = Some(draw_help_popup(f, app, chunks[1]));
+    }
+    if app.timer_state.idle_prompt.is_some() {
+        draw_idle_popup(f, app, chunks[1]);
+    }
+
+    if !app.enhanced_graphics {
+        f.render_widget(AsciiBorders, f.size());
     }
 }
 
-fn draw_new_task_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+/// Redraws the box drawing characters of borders and dividers in ASCII, `Block` itself
+/// only has unicode border sets.
+struct AsciiBorders;
+
+impl Widget for AsciiBorders {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                let ascii = match cell.symbol.as_str() {
+                    "─" | "━" | "═" => "-",
+                    "│" | "┃" | "║" => "|",
+                    "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "┏" | "┓" | "┗" | "┛" | "╔"
+                    | "╗" | "╚" | "╝" => "+",
+                    _ => continue,
+                };
+                cell.set_symbol(ascii);
+            }
+        }
+    }
+}
+
+/// Areas of the tab titles as `Tabs` lays them out: a space of padding around every title
+/// and a one column divider between them.
+fn tab_title_areas(titles: &[&str], area: Rect) -> Vec<Rect> {
+    let mut x = area.x + 1;
+    titles
+        .iter()
+        .map(|title| {
+            let width = title.width() as u16 + 2;
+            let title_area = Rect::new(x, area.y + 1, width, 1).intersection(area);
+            x += width + 1;
+            title_area
+        })
+        .collect()
+}
+
+/// Right-aligned status text inside the header: the latest notification, exceeded budgets
+/// and the running timer.
+fn draw_header_status<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if area.width <= 2 || area.height <= 2 {
+        return;
+    }
+
+    let theme = &app.theme;
+    let mut spans = vec![];
+    if let Some((message, _)) = &app.notification {
+        spans.push(Span::styled(
+            format!("{} ", message),
+            Style::default()
+                .fg(theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let exceeded_budget_count = app.timer_state.exceeded_budget_count();
+    if exceeded_budget_count > 0 {
+        spans.push(Span::styled(
+            format!(
+                "{} budget{} exceeded ",
+                exceeded_budget_count,
+                if exceeded_budget_count == 1 { "" } else { "s" }
+            ),
+            Style::default()
+                .fg(theme.warning)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    let running_timers = app.timer_state.running_timers();
+    if let Some(timer) = running_timers.first() {
+        let mut running = format!("● {} {}", timer.title, format_duration(timer.time_active()));
+        if running_timers.len() > 1 {
+            running.push_str(&format!(" (+{} more)", running_timers.len() - 1));
+        }
+        spans.push(Span::styled(
+            format!("{} ", running),
+            Style::default().fg(theme.active),
+        ));
+    }
+    if spans.is_empty() {
+        return;
+    }
+
+    let status_area = Rect::new(area.x + 1, area.y + 1, area.width - 2, 1);
+    let status = Paragraph::new(Spans::from(spans)).alignment(Alignment::Right);
+
+    f.render_widget(status, status_area);
+}
+
+fn draw_status_line<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    // Right aligned like vim's showcmd
+    let status_line = Paragraph::new(Span::styled(
+        format!("{} ", app.pending_keys),
+        Style::default()
+            .fg(app.theme.accent)
+            .add_modifier(Modifier::BOLD),
+    ))
+    .alignment(Alignment::Right);
+
+    f.render_widget(status_line, area);
+}
+
+fn draw_new_task_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Rect
+where
+    B: Backend,
+{
+    let title = match app.task_state.popup_target {
+        TaskPopupTarget::NewTask => "New Task",
+        TaskPopupTarget::NewSubtask(_) => "New Subtask",
+        TaskPopupTarget::EditTask(_) => "Edit Task",
+    };
+
+    let task_state = &mut app.task_state;
+    let fields = vec![
+        FormField {
+            label: "Title",
+            is_focused: task_state.focused_input == TaskPopupInput::Title,
+            input: &mut task_state.title_input,
+        },
+        FormField {
+            label: "Due Date (YYYY-MM-DD, today, tomorrow, +3d)",
+            is_focused: task_state.focused_input == TaskPopupInput::DueDate,
+            input: &mut task_state.due_date_input,
+        },
+        FormField {
+            label: "Tags (comma separated)",
+            is_focused: task_state.focused_input == TaskPopupInput::Tags,
+            input: &mut task_state.tags_input,
+        },
+        FormField {
+            label: "Project",
+            is_focused: task_state.focused_input == TaskPopupInput::Project,
+            input: &mut task_state.project_input,
+        },
+    ];
+
+    draw_form_popup(
+        f,
+        &app.theme,
+        title,
+        fields,
+        task_state.popup_error.as_deref(),
+        area,
+    )
+}
+
+fn draw_new_timer_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Rect
+where
+    B: Backend,
+{
+    let timer_state = &mut app.timer_state;
+    let title = if timer_state.creating_pomodoro {
+        "New Pomodoro"
+    } else {
+        "New Timer"
+    };
+    let mut fields = vec![FormField {
+        label: "Title",
+        is_focused: timer_state.focused_input == TimerPopupInput::Title,
+        input: &mut timer_state.title_input,
+    }];
+    if !timer_state.creating_pomodoro {
+        fields.push(FormField {
+            label: "Countdown (e.g. 25m or 1h30m)",
+            is_focused: timer_state.focused_input == TimerPopupInput::Duration,
+            input: &mut timer_state.duration_input,
+        });
+    }
+    fields.push(FormField {
+        label: "Tags (comma separated)",
+        is_focused: timer_state.focused_input == TimerPopupInput::Tags,
+        input: &mut timer_state.tags_input,
+    });
+    fields.push(FormField {
+        label: "Project",
+        is_focused: timer_state.focused_input == TimerPopupInput::Project,
+        input: &mut timer_state.project_input,
+    });
+
+    draw_form_popup(
+        f,
+        &app.theme,
+        title,
+        fields,
+        timer_state.popup_error.as_deref(),
+        area,
+    )
+}
+
+fn draw_adjust_timer_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Rect
 where
     B: Backend,
 {
-    let popup_chunk = centered_rect(60, 3, area);
+    let timer_state = &mut app.timer_state;
+    let focused_input = timer_state.adjust_focused_input;
+    let fields = vec![
+        FormField {
+            label: "Change total (+30m, -15m or =2h)",
+            is_focused: focused_input == AdjustPopupInput::Change,
+            input: &mut timer_state.change_input,
+        },
+        FormField {
+            label: "Day it applies to (empty for today, -1d or 2024-05-01)",
+            is_focused: focused_input == AdjustPopupInput::ChangeDate,
+            input: &mut timer_state.change_date_input,
+        },
+        FormField {
+            label: "Session #",
+            is_focused: focused_input == AdjustPopupInput::Session,
+            input: &mut timer_state.session_input,
+        },
+        FormField {
+            label: "Session start",
+            is_focused: focused_input == AdjustPopupInput::SessionStart,
+            input: &mut timer_state.session_start_input,
+        },
+        FormField {
+            label: "Session end (empty while running)",
+            is_focused: focused_input == AdjustPopupInput::SessionEnd,
+            input: &mut timer_state.session_end_input,
+        },
+    ];
 
+    draw_form_popup(
+        f,
+        &app.theme,
+        "Adjust Timer",
+        fields,
+        timer_state.popup_error.as_deref(),
+        area,
+    )
+}
+
+fn draw_export_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect) -> Rect
+where
+    B: Backend,
+{
+    let export_popup = &mut app.timer_state.export_popup;
+    let focused_input = export_popup.focused_input;
+    let fields = vec![
+        FormField {
+            label: "Format (csv, json or timewarrior)",
+            is_focused: focused_input == ExportPopupInput::Format,
+            input: &mut export_popup.format_input,
+        },
+        FormField {
+            label: "From (e.g. 2024-05-01, -7d)",
+            is_focused: focused_input == ExportPopupInput::From,
+            input: &mut export_popup.from_input,
+        },
+        FormField {
+            label: "To (e.g. today, yesterday)",
+            is_focused: focused_input == ExportPopupInput::To,
+            input: &mut export_popup.to_input,
+        },
+        FormField {
+            label: "Output file (empty for home directory)",
+            is_focused: focused_input == ExportPopupInput::Output,
+            input: &mut export_popup.output_input,
+        },
+    ];
+
+    draw_form_popup(
+        f,
+        &app.theme,
+        "Export Timesheet",
+        fields,
+        export_popup.error.as_deref(),
+        area,
+    )
+}
+
+fn draw_idle_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let timer_state = &app.timer_state;
+    let Some(idle_prompt) = &timer_state.idle_prompt else {
+        return;
+    };
+
+    let end = idle_prompt.end.unwrap_or_else(Local::now);
+    let idle_duration = (end - idle_prompt.start).to_std().unwrap_or_default();
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Spans::from(Span::raw(match idle_prompt.reason {
+            IdleReason::NoInput => format!(
+                "No input since {}, timers kept running.",
+                idle_prompt.start.format("%H:%M")
+            ),
+            IdleReason::Suspended => format!(
+                "App was not running from {} to {}.",
+                idle_prompt.start.format("%H:%M"),
+                end.format("%H:%M")
+            ),
+        })),
+        Spans::from(vec![
+            Span::raw("Idle time: "),
+            Span::styled(format_duration(idle_duration), bold),
+        ]),
+        Spans::default(),
+    ];
+
+    let keys = |action| {
+        app.keymap
+            .sequence(KeyScope::Idle, action)
+            .map(ToString::to_string)
+    };
+    match idle_prompt.assign_target {
+        None => lines.extend(
+            [
+                (Action::Keep, "keep it"),
+                (Action::Discard, "discard it"),
+                (Action::Assign, "assign it to another timer"),
+            ]
+            .into_iter()
+            .filter_map(|(action, description)| {
+                Some(Spans::from(vec![
+                    Span::styled(keys(action)?, bold),
+                    Span::raw(format!(" - {}", description)),
+                ]))
+            }),
+        ),
+        Some(target) => {
+            let move_keys: Vec<String> = [Action::MoveUp, Action::MoveDown]
+                .into_iter()
+                .filter_map(keys)
+                .collect();
+            let hints: Vec<String> = [
+                Some(move_keys.join("/")).filter(|keys| !keys.is_empty()),
+                keys(Action::Confirm),
+                keys(Action::Cancel).map(|keys| format!("{} to go back", keys)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            lines.push(Spans::from(Span::raw(format!(
+                "Assign to ({}):",
+                hints.join(", ")
+            ))));
+            lines.extend(
+                timer_state
+                    .timers
+                    .items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, timer)| {
+                        if index == target {
+                            Spans::from(Span::styled(
+                                format!("> {}", timer.title),
+                                Style::default()
+                                    .fg(app.theme.accent)
+                                    .add_modifier(Modifier::BOLD),
+                            ))
+                        } else {
+                            Spans::from(Span::raw(format!("  {}", timer.title)))
+                        }
+                    }),
+            );
+        }
+    };
+
+    let popup_chunk = centered_rect(60, lines.len() as u16 + 2, area);
+    let popup = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title("Idle Time")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Plain),
+        )
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(Clear, popup_chunk);
+    f.render_widget(popup, popup_chunk);
+}
+
+/// Lists the bindings keys currently go through, e.g. search results, the open tab and the
+/// global ones, or the input and popup ones while editing. Straight from the keymap so it
+/// shows the user's own keys.
+fn draw_help_popup<B>(f: &mut Frame<B>, app: &App, area: Rect) -> Rect
+where
+    B: Backend,
+{
+    let tab_title = app.tabs.titles[app.tabs.index];
+    let sections: Vec<(&str, KeyScope)> = app
+        .active_scopes()
+        .into_iter()
+        .map(|scope| match scope {
+            KeyScope::Search => ("Search results", scope),
+            KeyScope::Input => ("Text input", scope),
+            KeyScope::Popup => ("Popups", scope),
+            KeyScope::Global => ("Global", scope),
+            _ => (tab_title, scope),
+        })
+        .collect();
+    let mut lines = help_lines(&app.theme, &app.keymap, &sections);
+
+    // Lines flow into as many columns as the height of the screen needs
+    let rows = (lines.len() as u16)
+        .min(area.height.saturating_sub(2))
+        .max(1) as usize;
+    let column_count = lines.len().div_ceil(rows);
+    let rows = lines.len().div_ceil(column_count);
+    let popup_chunk = centered_rect(90, rows as u16 + 2, area);
     let block = Block::default()
-        .title("New Task")
+        .title(format!("Keys - {} (press any key to close)", tab_title))
         .borders(Borders::ALL)
         .border_type(BorderType::Plain);
-
-    let title_input = Paragraph::new(app.task_state.new_task.title.to_owned()).block(block);
+    let inner_area = block.inner(popup_chunk);
 
     f.render_widget(Clear, popup_chunk);
-    f.render_widget(title_input, popup_chunk);
+    f.render_widget(block, popup_chunk);
 
-    f.set_cursor(
-        popup_chunk.x + app.task_state.new_task.title.width() as u16 + 1,
-        popup_chunk.y + 1,
-    );
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, column_count as u32);
+            column_count
+        ])
+        .split(inner_area);
+    for chunk in chunks.iter() {
+        let rest = lines.split_off(rows.min(lines.len()));
+        let column = std::mem::replace(&mut lines, rest);
+        // A column of space to the next one
+        let column_area = Rect {
+            width: chunk.width.saturating_sub(1),
+            ..*chunk
+        };
+        f.render_widget(Paragraph::new(column), column_area);
+    }
+
+    popup_chunk
+}
+
+/// Titled sections with a line per bound action, keys left and descriptions lined up. Keys
+/// of a section hide the same keys in the sections after it, as they never reach those.
+fn help_lines<'a>(
+    theme: &Theme,
+    keymap: &'a Keymap,
+    sections: &[(&'a str, KeyScope)],
+) -> Vec<Spans<'a>> {
+    let mut taken_sequences = vec![];
+    let mut entries: Vec<Vec<(String, &str)>> = vec![];
+    for (_, scope) in sections {
+        let bindings = keymap.bindings(*scope);
+        entries.push(
+            bindings
+                .iter()
+                .filter_map(|binding| {
+                    let keys: Vec<String> = binding
+                        .sequences
+                        .iter()
+                        .filter(|sequence| !taken_sequences.contains(sequence))
+                        .map(ToString::to_string)
+                        .collect();
+                    (!keys.is_empty()).then(|| (keys.join(", "), binding.description))
+                })
+                .collect(),
+        );
+        taken_sequences.extend(bindings.iter().flat_map(|binding| &binding.sequences));
+    }
+    let keys_width = entries
+        .iter()
+        .flatten()
+        .map(|(keys, _)| keys.width())
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = Vec::new();
+    for ((title, _), entries) in sections.iter().zip(entries) {
+        if !lines.is_empty() {
+            lines.push(Spans::default());
+        }
+        lines.push(Spans::from(Span::styled(
+            *title,
+            Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        )));
+        lines.extend(entries.into_iter().map(|(keys, description)| {
+            Spans::from(vec![
+                Span::styled(
+                    format!("{:<width$}  ", keys, width = keys_width),
+                    Style::default().fg(theme.accent),
+                ),
+                Span::styled(description, Style::default().fg(theme.muted)),
+            ])
+        }));
+    }
+
+    lines
 }
 
-fn draw_new_timer_popup<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+/// Message for an empty list, naming the keys that create an item and open the help.
+fn empty_list_hint(keymap: &Keymap, scope: KeyScope, item_name: &str) -> String {
+    let mut hint = format!("You don't have any {}!", item_name);
+    if let Some(keys) = keymap.sequence(scope, Action::New) {
+        hint.push_str(&format!(" Create one using ('{}' key).", keys));
+    }
+    if let Some(keys) = keymap.sequence(KeyScope::Global, Action::Help) {
+        hint.push_str(&format!(" Press '{}' to see all keys.", keys));
+    }
+
+    hint
+}
+
+/// Labelled input rendered inside `draw_form_popup`.
+struct FormField<'a> {
+    label: &'a str,
+    input: &'a mut TextInput,
+    is_focused: bool,
+}
+
+fn draw_form_popup<B>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    title: &str,
+    fields: Vec<FormField>,
+    error: Option<&str>,
+    area: Rect,
+) -> Rect
 where
     B: Backend,
 {
-    let popup_chunk = centered_rect(60, 3, area);
+    let error_height = if error.is_some() { 1 } else { 0 };
+    let popup_chunk = centered_rect(60, fields.len() as u16 * 3 + error_height + 2, area);
 
     let block = Block::default()
-        .title("New Timer")
+        .title(title)
         .borders(Borders::ALL)
         .border_type(BorderType::Plain);
-
-    let title_input = Paragraph::new(app.timer_state.new_timer.title.to_owned()).block(block);
+    let inner_area = block.inner(popup_chunk);
 
     f.render_widget(Clear, popup_chunk);
-    f.render_widget(title_input, popup_chunk);
+    f.render_widget(block, popup_chunk);
 
-    f.set_cursor(
-        popup_chunk.x + app.timer_state.new_timer.title.width() as u16 + 1,
-        popup_chunk.y + 1,
-    );
+    let mut constraints = vec![Constraint::Length(3); fields.len()];
+    constraints.push(Constraint::Length(error_height));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner_area);
+
+    for (field, chunk) in fields.into_iter().zip(chunks.iter()) {
+        let field_block = Block::default()
+            .title(field.label)
+            .borders(Borders::ALL)
+            .border_style(if field.is_focused {
+                Style::default().fg(theme.accent)
+            } else {
+                Style::default().fg(theme.muted)
+            });
+
+        let field_area = field_block.inner(*chunk);
+        let (visible_value, cursor_column) = field.input.visible(field_area.width as usize);
+
+        f.render_widget(Paragraph::new(visible_value).block(field_block), *chunk);
+
+        if field.is_focused {
+            f.set_cursor(field_area.x + cursor_column as u16, field_area.y);
+        }
+    }
+
+    if let Some(error) = error {
+        let error_information =
+            Paragraph::new(Span::styled(error, Style::default().fg(theme.error)));
+        f.render_widget(error_information, chunks[chunks.len() - 1]);
+    }
+
+    popup_chunk
+}
+
+fn draw_list_input_bar<B>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    title: &str,
+    input: &mut TextInput,
+    area: Rect,
+) where
+    B: Backend,
+{
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme.accent));
+
+    let input_area = block.inner(area);
+    let (visible_value, cursor_column) = input.visible(input_area.width as usize);
+
+    f.render_widget(Paragraph::new(visible_value).block(block), area);
+    f.set_cursor(input_area.x + cursor_column as u16, input_area.y);
+}
+
+/// Draws whichever input bar is open below a list.
+fn draw_list_input_bars<B>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    filter_bar: &mut FilterBar,
+    search_bar: &mut SearchBar,
+    area: Option<Rect>,
+) where
+    B: Backend,
+{
+    if let Some(area) = area {
+        if filter_bar.is_enabled {
+            draw_list_input_bar(
+                f,
+                theme,
+                "Filter (#tag @project text)",
+                &mut filter_bar.input,
+                area,
+            );
+        } else if search_bar.is_enabled {
+            draw_list_input_bar(f, theme, "Search", &mut search_bar.input, area);
+        }
+    }
+}
+
+/// Splits off room for an input bar below a list while one is open.
+fn split_input_bar(is_open: bool, area: Rect) -> (Rect, Option<Rect>) {
+    if !is_open {
+        return (area, None);
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    (chunks[0], Some(chunks[1]))
+}
+
+fn list_title(
+    title: &str,
+    filter_bar: &FilterBar,
+    search_bar: &SearchBar,
+    keymap: &Keymap,
+) -> String {
+    let mut list_title = String::from(title);
+    if let Some(query) = filter_bar.query() {
+        list_title.push_str(&format!(" [filter: {}]", query));
+    }
+    if let Some(query) = &search_bar.query {
+        let keys = |action| {
+            keymap
+                .sequence(KeyScope::Search, action)
+                .map(ToString::to_string)
+        };
+        let jump_keys: Vec<String> = [Action::NextMatch, Action::PreviousMatch]
+            .into_iter()
+            .filter_map(keys)
+            .collect();
+        let hints: Vec<String> = [
+            Some(jump_keys.join("/"))
+                .filter(|keys| !keys.is_empty())
+                .map(|keys| format!("{} to jump", keys)),
+            keys(Action::Cancel).map(|keys| format!("{} to clear", keys)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        list_title.push_str(&format!(" [search: {}", query));
+        if !hints.is_empty() {
+            list_title.push_str(&format!(" - {}", hints.join(", ")));
+        }
+        list_title.push(']');
+    }
+
+    list_title
+}
+
+/// Quoted title with the characters matched by the search query highlighted.
+fn highlighted_title(title: &str, query: Option<&str>, theme: &Theme) -> Vec<Span<'static>> {
+    let matched_indices = query
+        .and_then(|query| fuzzy_match(query, title))
+        .unwrap_or_default();
+    let highlight_style = Style::default()
+        .fg(theme.accent)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    let mut spans = vec![Span::raw("\"")];
+    spans.extend(title.chars().enumerate().map(|(index, c)| {
+        if matched_indices.contains(&index) {
+            Span::styled(c.to_string(), highlight_style)
+        } else {
+            Span::raw(c.to_string())
+        }
+    }));
+    spans.push(Span::raw("\""));
+
+    spans
+}
+
+fn labels_text(tags: &[String], project: Option<&str>) -> Option<String> {
+    let labels: Vec<String> = tags
+        .iter()
+        .map(|tag| format!("#{}", tag))
+        .chain(project.map(|project| format!("@{}", project)))
+        .collect();
+
+    if labels.is_empty() {
+        None
+    } else {
+        Some(labels.join(" "))
+    }
 }
 
 fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
-    // TODO: Might have some edge cases with specific viewports
-    let empty_space = ((r.height - height) * 100) / r.height / 2;
+    let height = height.min(r.height);
+    let empty_space = r.height - height;
 
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Percentage(empty_space),
-                Constraint::Percentage(100 - empty_space * 2),
-                Constraint::Percentage(empty_space),
+                Constraint::Length(empty_space / 2),
+                Constraint::Length(height),
+                Constraint::Min(0),
             ]
             .as_ref(),
         )
@@ -126,85 +862,377 @@ fn draw_task_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
-        .split(area);
+    let theme = app.theme;
+    let task_state = &app.task_state;
+    let (list_area, input_bar_area) = split_input_bar(
+        task_state.filter_bar.is_enabled || task_state.search_bar.is_enabled,
+        area,
+    );
 
-    let task_list_block = Block::default().borders(Borders::ALL).title("Task List");
+    let task_list_title = match app.task_state.sort_mode {
+        TaskSortMode::Insertion => String::from("Task List"),
+        sort_mode => format!("Task List (sorted by {})", sort_mode.label()),
+    };
+    let task_list_block = Block::default().borders(Borders::ALL).title(list_title(
+        &task_list_title,
+        &task_state.filter_bar,
+        &task_state.search_bar,
+        &app.keymap,
+    ));
 
-    let tasks: Vec<ListItem> = app
-        .task_state
-        .tasks
-        .items
+    let today = Local::now().date_naive();
+    let time_per_task = app.timer_state.time_per_task();
+    let search_query = task_state.search_bar.highlighted_query();
+    let task_tree = &task_state.tasks;
+    let tasks: Vec<ListItem> = task_tree
+        .rows()
         .iter()
-        .map(|task| {
-            ListItem::new(vec![Spans::from(vec![
+        .filter_map(|path| task_tree.get(path).map(|task| (path.len() - 1, task)))
+        .map(|(depth, task)| {
+            let expander = if task.children.is_empty() {
+                "  "
+            } else if task.is_collapsed {
+                "▸ "
+            } else {
+                "▾ "
+            };
+
+            let mut spans = vec![
+                Span::raw("  ".repeat(depth)),
+                Span::raw(expander),
                 Span::raw(if task.is_completed { "[*]" } else { "[ ]" }),
                 Span::raw(" - "),
-                Span::raw(format!("\"{}\"", &task.title)),
-            ])])
-            .style(Style::default().fg(if task.is_completed {
-                Color::Green
-            } else {
-                Color::Red
-            }))
+            ];
+            spans.extend(highlighted_title(&task.title, search_query, &theme));
+            if let Some(priority) = task.priority.label() {
+                spans.push(Span::styled(
+                    format!(" [{}]", priority),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ));
+            }
+            if !task.children.is_empty() {
+                let (completed, total) = task.completion_ratio();
+                spans.push(Span::raw(format!(" ({}/{})", completed, total)));
+            }
+            if let Some(labels) = labels_text(&task.tags, task.project.as_deref()) {
+                spans.push(Span::styled(
+                    format!(" {}", labels),
+                    Style::default().fg(theme.label),
+                ));
+            }
+            if let Some(tracked_time) = time_per_task.get(&task.id) {
+                spans.push(Span::styled(
+                    format!(
+                        " [{}{}]",
+                        format_duration(tracked_time.total),
+                        if tracked_time.is_running {
+                            " running"
+                        } else {
+                            ""
+                        }
+                    ),
+                    Style::default().fg(theme.tracked),
+                ));
+            }
+
+            let due_status = task.due_status(today);
+            if let Some(due_status) = &due_status {
+                spans.push(Span::styled(
+                    match due_status {
+                        DueStatus::Overdue(days) => format!(" - overdue by {}d", days),
+                        DueStatus::Today => String::from(" - due today"),
+                        DueStatus::Upcoming(days) => format!(" - due in {}d", days),
+                    },
+                    Style::default().add_modifier(Modifier::ITALIC),
+                ));
+            }
+
+            let style = match (task.is_completed, due_status) {
+                (true, _) => Style::default().fg(theme.active),
+                (false, Some(DueStatus::Overdue(_))) => Style::default()
+                    .fg(theme.warning)
+                    .add_modifier(Modifier::BOLD),
+                (false, Some(DueStatus::Today)) => Style::default().fg(theme.accent),
+                (false, Some(DueStatus::Upcoming(_))) => Style::default().fg(theme.info),
+                (false, None) => Style::default().fg(theme.inactive),
+            };
+
+            ListItem::new(vec![Spans::from(spans)]).style(style)
         })
         .collect();
 
     if tasks.is_empty() {
         let empty_information = Paragraph::new(Span::styled(
-            "You don't have any tasks! Create one using ('n' key).",
+            if task_state.tasks.items.is_empty() {
+                empty_list_hint(&app.keymap, KeyScope::Tasks, "tasks")
+            } else {
+                String::from("No tasks match the filter.")
+            },
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.accent)
                 .add_modifier(Modifier::ITALIC),
         ))
-        .block(task_list_block);
+        .block(task_list_block)
+        .wrap(Wrap { trim: true });
 
-        f.render_widget(empty_information, chunks[0]);
+        f.render_widget(empty_information, list_area);
     } else {
-        let tasks = List::new(tasks).block(task_list_block).highlight_style(
-            Style::default()
-                .bg(Color::Rgb(50, 50, 50))
-                .add_modifier(Modifier::BOLD),
+        // Drawn as a window like the timer list so clicks can be matched to rows.
+        let inner_area = task_list_block.inner(list_area);
+        let heights: Vec<usize> = tasks.iter().map(ListItem::height).collect();
+        let task_tree = &mut app.task_state.tasks;
+        let window = task_tree.scroll_window(&heights, inner_area.height as usize);
+        let mut window_state = ListState::default();
+        window_state.select(
+            task_tree
+                .state
+                .selected()
+                .and_then(|selected| selected.checked_sub(window.start)),
         );
 
-        f.render_stateful_widget(tasks, chunks[0], &mut app.task_state.tasks.state);
+        let tasks = List::new(tasks[window.clone()].to_vec())
+            .block(task_list_block)
+            .highlight_style(theme.selection_style().add_modifier(Modifier::BOLD));
+        f.render_stateful_widget(tasks, list_area, &mut window_state);
+
+        app.mouse_areas.list_rows = list_row_areas(&heights, window, inner_area);
+    }
+
+    let task_state = &mut app.task_state;
+    draw_list_input_bars(
+        f,
+        &theme,
+        &mut task_state.filter_bar,
+        &mut task_state.search_bar,
+        input_bar_area,
+    );
+}
+
+/// Area of every row in the window with its row number, stacked from the top of
+/// `list_area` and clipped to it.
+fn list_row_areas(heights: &[usize], window: Range<usize>, list_area: Rect) -> Vec<(Rect, usize)> {
+    let mut row_y = list_area.y;
+    window
+        .map(|row| {
+            let row_area = Rect::new(list_area.x, row_y, list_area.width, heights[row] as u16);
+            row_y += heights[row] as u16;
+            (row_area.intersection(list_area), row)
+        })
+        .collect()
+}
+
+const EXPIRY_FLASH_MILLISECONDS: i64 = 5000;
+
+/// Gauge drawn over a line of a list row, right after the line's `label`.
+struct RowGauge {
+    line: usize,
+    label: &'static str,
+    ratio: f64,
+    text: String,
+    color: Color,
+}
+
+impl RowGauge {
+    /// Line of the list row the gauge is drawn over.
+    fn label_line(&self) -> Spans<'static> {
+        Spans::from(Span::styled(
+            self.label,
+            Style::default().add_modifier(Modifier::BOLD),
+        ))
+    }
+}
+
+fn draw_row_gauge<B: Backend>(
+    f: &mut Frame<B>,
+    theme: &Theme,
+    gauge: &RowGauge,
+    row_y: u16,
+    list_area: Rect,
+) {
+    let label_width = gauge.label.len() as u16;
+    let y = row_y + gauge.line as u16;
+    if y >= list_area.bottom() || list_area.width <= label_width {
+        return;
     }
+
+    let ratio = gauge.ratio.clamp(0.0, 1.0);
+    let gauge_widget = Gauge::default()
+        .gauge_style(Style::default().fg(gauge.color).bg(theme.background))
+        .ratio(ratio)
+        .label(gauge.text.as_str());
+    f.render_widget(
+        gauge_widget,
+        Rect::new(
+            list_area.x + label_width,
+            y,
+            list_area.width - label_width,
+            1,
+        ),
+    );
+}
+
+/// Sessions of the selected timer grouped by day, with day subtotals and this week's total.
+fn draw_timer_detail<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let timer_state = &app.timer_state;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Sessions");
+
+    let Some(timer) = timer_state
+        .timers
+        .selected_index()
+        .map(|index| &timer_state.timers.items[index])
+    else {
+        let placeholder = Paragraph::new(Span::styled(
+            "Select a timer to see its sessions.",
+            Style::default()
+                .fg(app.theme.accent)
+                .add_modifier(Modifier::ITALIC),
+        ))
+        .block(block)
+        .wrap(Wrap { trim: true });
+        f.render_widget(placeholder, area);
+        return;
+    };
+
+    let now = Local::now();
+    let today = now.date_naive();
+    let week_start = start_of_week(today);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::styled("This week: ", bold),
+            Span::raw(format_duration(
+                timer.time_active_between(start_of_day(week_start), now),
+            )),
+        ]),
+        Spans::from(vec![
+            Span::styled("Total: ", bold),
+            Span::raw(format_duration(timer.time_active())),
+        ]),
+    ];
+    for day in timer.days() {
+        let date = day.date;
+        let day_label = match (today - date).num_days() {
+            0 => "Today".to_owned(),
+            1 => "Yesterday".to_owned(),
+            _ => date.format("%a %Y-%m-%d").to_string(),
+        };
+
+        lines.push(Spans::default());
+        lines.push(Spans::from(vec![
+            Span::styled(format!("{}: ", day_label), bold),
+            Span::raw(format_duration(timer.time_active_on(date))),
+        ]));
+        for session in day.sessions {
+            let end = match session.end {
+                // The part of a session running past midnight
+                Some(end) if end.date_naive() != date => "24:00".to_owned(),
+                Some(end) => end.format("%H:%M").to_string(),
+                None => "now".to_owned(),
+            };
+            lines.push(Spans::from(Span::raw(format!(
+                " #{:<3} {} - {:<5}  {}",
+                session.index + 1,
+                session.start.format("%H:%M"),
+                end,
+                format_duration(session.duration())
+            ))));
+        }
+        if day.offset_seconds != 0 {
+            let sign = if day.offset_seconds < 0 { '-' } else { '+' };
+            lines.push(Spans::from(Span::styled(
+                format!(
+                    " adjusted      {}{}",
+                    sign,
+                    format_duration(Duration::from_secs(day.offset_seconds.unsigned_abs()))
+                ),
+                Style::default().fg(app.theme.muted),
+            )));
+        }
+    }
+    if timer.sessions.is_empty() {
+        lines.push(Spans::default());
+        lines.push(Spans::from(Span::styled(
+            "Not started yet.",
+            Style::default().add_modifier(Modifier::ITALIC),
+        )));
+    }
+    if !timer.adjustments.is_empty() {
+        lines.push(Spans::default());
+        lines.push(Spans::from(Span::styled("Adjustments:", bold)));
+        for adjustment in timer.adjustments.iter().rev() {
+            lines.push(Spans::from(vec![
+                Span::styled(
+                    format!(" {} ", adjustment.made_at.format("%Y-%m-%d %H:%M")),
+                    Style::default().fg(app.theme.muted),
+                ),
+                Span::raw(adjustment.description.clone()),
+            ]));
+        }
+    }
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
 }
 
 fn draw_timers_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
+    let theme = app.theme;
+    let timer_state = &app.timer_state;
+    let (area, input_bar_area) = split_input_bar(
+        timer_state.filter_bar.is_enabled || timer_state.search_bar.is_enabled,
+        area,
+    );
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
         .split(area);
+    let list_area = chunks[0];
+    draw_timer_detail(f, app, chunks[1]);
+    let timer_state = &app.timer_state;
 
-    let timers: Vec<ListItem> = app
-        .timer_state
-        .timers
-        .items
+    let search_query = timer_state.search_bar.highlighted_query();
+    let timer_list = &timer_state.timers;
+    let now = Local::now();
+    let expired_status = match app.keymap.sequence(KeyScope::Timers, Action::Toggle) {
+        Some(keys) => format!("[Expired] ({} to acknowledge)", keys),
+        None => String::from("[Expired]"),
+    };
+    let (timers, gauges): (Vec<ListItem>, Vec<Vec<RowGauge>>) = timer_list
+        .visible_indices()
         .iter()
+        .map(|index| &timer_list.items[*index])
         .map(|timer| {
-            let timer_seconds = timer.time_active.as_secs();
-            let formatted_active_time_information = format!(
-                "{:02}:{:02}:{:02}",
-                timer_seconds / 3600,
-                timer_seconds / 60,
-                timer_seconds % 60
-            );
+            let formatted_active_time_information = format_duration(timer.time_active());
 
-            let lines = vec![
-                Spans::from(vec![
-                    Span::styled("Title: ", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(format!("\"{}\"", &timer.title)),
-                ]),
+            let mut lines = vec![
+                Spans::from(
+                    [
+                        vec![Span::styled(
+                            "Title: ",
+                            Style::default().add_modifier(Modifier::BOLD),
+                        )],
+                        highlighted_title(&timer.title, search_query, &theme),
+                    ]
+                    .concat(),
+                ),
                 Spans::from(vec![
                     Span::styled(" - Status: ", Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(if timer.is_active {
+                    Span::raw(if timer.is_expired() {
+                        expired_status.as_str()
+                    } else if timer.is_active() {
                         "[Active]"
+                    } else if timer.resumes_after_break() {
+                        "[Break]"
                     } else {
                         "[Inactive]"
                     }),
@@ -216,43 +1244,277 @@ where
                     ),
                     Span::raw(formatted_active_time_information),
                 ]),
-                Spans::from(vec![
+            ];
+            if let (TimerKind::Pomodoro(pomodoro), Some(remaining)) = (
+                &timer.kind,
+                timer.pomodoro_remaining(&timer_state.pomodoro_config),
+            ) {
+                lines.push(Spans::from(vec![
                     Span::styled(
-                        " - Creation Date: ",
+                        " - Pomodoro: ",
                         Style::default().add_modifier(Modifier::BOLD),
                     ),
-                    Span::raw(timer.time_created.to_string()),
-                ]),
-            ];
+                    Span::raw(format!(
+                        "{}, {} left ({} focus sessions today)",
+                        pomodoro.phase.label(),
+                        format_duration(remaining),
+                        pomodoro.completed_on(Local::now().date_naive())
+                    )),
+                ]));
+            }
+            let mut gauges = vec![];
+            if let (TimerKind::Countdown(countdown), Some(remaining)) =
+                (&timer.kind, timer.countdown_remaining())
+            {
+                lines.push(Spans::from(vec![
+                    Span::styled(
+                        " - Remaining: ",
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(format!(
+                        "{} of {}",
+                        format_duration(remaining),
+                        format_duration(countdown.target)
+                    )),
+                ]));
+                let ratio = 1.0 - remaining.as_secs_f64() / countdown.target.as_secs_f64();
+                let gauge = RowGauge {
+                    line: lines.len(),
+                    label: " - Progress: ",
+                    ratio,
+                    text: format!("{:.0}%", ratio.clamp(0.0, 1.0) * 100.0),
+                    color: if timer.is_expired() {
+                        theme.expired
+                    } else {
+                        theme.info
+                    },
+                };
+                lines.push(gauge.label_line());
+                gauges.push(gauge);
+            }
+            if let (Some(budget), Some(used)) = (timer.budget, timer.budget_used()) {
+                let gauge = RowGauge {
+                    line: lines.len(),
+                    label: " - Budget: ",
+                    ratio: used.as_secs_f64() / budget.limit.as_secs_f64(),
+                    text: format!(
+                        "{} of {} {}",
+                        format_duration(used),
+                        format_duration(budget.limit),
+                        budget.period.label()
+                    ),
+                    color: if timer.is_over_budget() {
+                        theme.warning
+                    } else {
+                        theme.label
+                    },
+                };
+                lines.push(gauge.label_line());
+                gauges.push(gauge);
+            }
+            lines.push(Spans::from(vec![
+                Span::styled(
+                    " - Creation Date: ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(timer.time_created.to_string()),
+            ]));
+            if let Some(labels) = labels_text(&timer.tags, timer.project.as_deref()) {
+                lines.push(Spans::from(vec![
+                    Span::styled(" - Labels: ", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::styled(labels, Style::default().fg(theme.label)),
+                ]));
+            }
 
-            ListItem::new(lines).style(Style::default().fg(if timer.is_active {
-                Color::Green
+            let mut style = Style::default().fg(if timer.is_expired() {
+                theme.expired
+            } else if timer.is_over_budget() {
+                theme.warning
+            } else if timer.is_active() {
+                theme.active
             } else {
-                Color::Red
-            }))
+                theme.inactive
+            });
+            // Freshly expired countdowns blink for a moment on top of staying marked.
+            if let TimerKind::Countdown(Countdown {
+                expired_at: Some(expired_at),
+                ..
+            }) = &timer.kind
+            {
+                let since_expiry = (now - *expired_at).num_milliseconds();
+                if since_expiry < EXPIRY_FLASH_MILLISECONDS && (since_expiry / 500) % 2 == 0 {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+            }
+
+            (ListItem::new(lines).style(style), gauges)
         })
-        .collect();
+        .unzip();
 
     let timer_list_block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .title("Timer List");
+        .title(list_title(
+            "Timer List",
+            &timer_state.filter_bar,
+            &timer_state.search_bar,
+            &app.keymap,
+        ));
 
     if timers.is_empty() {
         let empty_information = Paragraph::new(Span::styled(
-            "You don't have any timers! Create one using ('n' key).",
+            if timer_state.timers.items.is_empty() {
+                empty_list_hint(&app.keymap, KeyScope::Timers, "timers")
+            } else {
+                String::from("No timers match the filter.")
+            },
             Style::default()
-                .fg(Color::Yellow)
+                .fg(theme.accent)
                 .add_modifier(Modifier::ITALIC),
         ))
-        .block(timer_list_block);
+        .block(timer_list_block)
+        .wrap(Wrap { trim: true });
 
-        f.render_widget(empty_information, chunks[0]);
+        f.render_widget(empty_information, list_area);
     } else {
-        let timers = List::new(timers)
+        // Only the scrolled window is handed to the list so gauges can be placed on its rows.
+        let inner_area = timer_list_block.inner(list_area);
+        let heights: Vec<usize> = timers.iter().map(ListItem::height).collect();
+        let timer_list = &mut app.timer_state.timers;
+        let window = timer_list.scroll_window(&heights, inner_area.height as usize);
+        let mut window_state = ListState::default();
+        window_state.select(
+            timer_list
+                .state
+                .selected()
+                .and_then(|selected| selected.checked_sub(window.start)),
+        );
+
+        let timers = List::new(timers[window.clone()].to_vec())
             .block(timer_list_block)
-            .highlight_style(Style::default().bg(Color::Rgb(50, 50, 50)));
+            .highlight_style(theme.selection_style());
+        f.render_stateful_widget(timers, list_area, &mut window_state);
+
+        let mut row_y = inner_area.y;
+        for (row_gauges, height) in gauges[window.clone()].iter().zip(&heights[window.clone()]) {
+            for gauge in row_gauges {
+                draw_row_gauge(f, &theme, gauge, row_y, inner_area);
+            }
+            row_y += *height as u16;
+        }
 
-        f.render_stateful_widget(timers, chunks[0], &mut app.timer_state.timers.state);
+        app.mouse_areas.list_rows = list_row_areas(&heights, window, inner_area);
     }
+
+    let timer_state = &mut app.timer_state;
+    draw_list_input_bars(
+        f,
+        &theme,
+        &mut timer_state.filter_bar,
+        &mut timer_state.search_bar,
+        input_bar_area,
+    );
+}
+
+fn draw_reports_tab<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = app.theme;
+    let range = app.report_state.range;
+    let today = Local::now().date_naive();
+    let dates = range.dates(today);
+    let timers = &app.timer_state.timers.items;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+    let bottom_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(chunks[1]);
+
+    let report_block = |title: String| {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+    };
+
+    // Time per day, labelled with the day of month when the bars are wide enough.
+    let tracked_per_day = tracked_per_day(timers, &dates);
+    let day_bar_width = bar_width(chunks[0], dates.len());
+    let day_labels: Vec<String> = dates
+        .iter()
+        .map(|date| {
+            if day_bar_width >= 2 {
+                date.format("%d").to_string()
+            } else {
+                String::new()
+            }
+        })
+        .collect();
+    let day_data: Vec<(&str, u64)> = day_labels
+        .iter()
+        .zip(&tracked_per_day)
+        .map(|(label, (_, duration))| (label.as_str(), duration.as_secs() / 60))
+        .collect();
+    let day_chart = BarChart::default()
+        .block(report_block(
+            match app.keymap.sequence(KeyScope::Reports, Action::ToggleRange) {
+                Some(keys) => format!(
+                    "Tracked minutes per day ({}, '{}' to change)",
+                    range.label(),
+                    keys
+                ),
+                None => format!("Tracked minutes per day ({})", range.label()),
+            },
+        ))
+        .data(&day_data)
+        .bar_width(day_bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(theme.active))
+        .value_style(Style::default().fg(theme.background).bg(theme.active));
+    f.render_widget(day_chart, chunks[0]);
+
+    // Time per timer, as many of the longest ones as fit.
+    let tracked_per_timer = tracked_per_timer(timers, dates[0]);
+    let timer_bar_width = 8;
+    let timer_count =
+        ((bottom_chunks[0].width.saturating_sub(2) + 1) / (timer_bar_width + 1)) as usize;
+    let timer_data: Vec<(&str, u64)> = tracked_per_timer
+        .iter()
+        .take(timer_count)
+        .map(|(title, duration)| (*title, duration.as_secs() / 60))
+        .collect();
+    let timer_chart = BarChart::default()
+        .block(report_block(String::from("Tracked minutes per timer")))
+        .data(&timer_data)
+        .bar_width(timer_bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(theme.info))
+        .value_style(Style::default().fg(theme.background).bg(theme.info));
+    f.render_widget(timer_chart, bottom_chunks[0]);
+
+    // Completed tasks per day.
+    let completed: Vec<u64> = completed_per_day(&app.task_state.tasks.items, &dates)
+        .into_iter()
+        .map(|(_, count)| count)
+        .collect();
+    let completed_total: u64 = completed.iter().sum();
+    let completed_sparkline = Sparkline::default()
+        .block(report_block(format!(
+            "Completed tasks ({})",
+            completed_total
+        )))
+        .data(&completed)
+        .style(Style::default().fg(theme.accent));
+    f.render_widget(completed_sparkline, bottom_chunks[1]);
+}
+
+/// Widest bars that still fit `bar_count` bars with a gap of one into the chart.
+fn bar_width(area: Rect, bar_count: usize) -> u16 {
+    let inner_width = area.width.saturating_sub(2) as usize;
+    (inner_width / bar_count.max(1)).saturating_sub(1).max(1) as u16
 }